@@ -67,6 +67,11 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
 async fn handle_empty_order(table_id: u32, client_clone: &RestaurantClient) {
     println!("No orders found for table id {}", table_id);
+    let guests = rng().random_range(1..=4);
+    if let Err(e) = client_clone.open_session(table_id, guests).await {
+        eprintln!("Error seating guests at table {}: {}", table_id, e);
+        return;
+    }
     let menu_count = rng().random_range(1..=3);
     if let Err(e) = client_clone.create_order(table_id, menu_count).await {
         eprintln!("Error creating order for table {}: {}", table_id, e);
@@ -74,7 +79,7 @@ async fn handle_empty_order(table_id: u32, client_clone: &RestaurantClient) {
 }
 
 async fn specific_operation(table_id: u32, client_clone: RestaurantClient, orders: Vec<Order>) {
-    let action = rng().random_range(0..3);
+    let action = rng().random_range(0..4);
     let result = match action {
         0 => {
             let menu_count = rng().random_range(1..=2);
@@ -88,6 +93,7 @@ async fn specific_operation(table_id: u32, client_clone: RestaurantClient, order
                 Ok(())
             }
         }
+        2 => client_clone.close_session(table_id).await,
         _ => {
             if !orders.is_empty() {
                 let idx = rng().random_range(0..orders.len());
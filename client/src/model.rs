@@ -13,6 +13,14 @@ pub struct MenuData {
     pub name: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderLine {
+    pub menu_id: Uuid,
+    pub quantity: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub table_range: (u32, u32),
@@ -23,6 +31,10 @@ pub struct Order {
     pub id: Uuid,
     pub table_id: u32,
     pub menu: MenuData,
+    #[serde(default)]
+    pub quantity: u32,
+    #[serde(default)]
+    pub note: Option<String>,
     pub cooking_time_minutes: u32,
     pub created_at: String,
 }
@@ -1,4 +1,4 @@
-use crate::model::{ApiResponse, Config, MenuData, Order};
+use crate::model::{ApiResponse, Config, MenuData, Order, OrderLine};
 use rand::Rng;
 use reqwest::Client;
 use std::error::Error;
@@ -69,10 +69,17 @@ impl RestaurantClient {
     }
 
     pub async fn create_order(&self, table_id: u32, menu_count: usize) -> Result<()> {
-        let selected_menus: Vec<MenuData> = {
+        let selected_menus: Vec<OrderLine> = {
             let mut rng = rand::rng();
             (0..menu_count)
-                .map(|_| self.available_menus[rng.random_range(0..self.available_menus.len())].clone())
+                .map(|_| {
+                    let menu = &self.available_menus[rng.random_range(0..self.available_menus.len())];
+                    OrderLine {
+                        menu_id: menu.id,
+                        quantity: rng.random_range(1..=3),
+                        note: rng.random_bool(0.2).then(|| "no green onion".to_string()),
+                    }
+                })
                 .collect()
         };
         println!("Creating order for table {}", table_id);
@@ -90,6 +97,30 @@ impl RestaurantClient {
         Ok(())
     }
 
+    /// Seat a party. A table that is already taken answers 409, which is fine here.
+    pub async fn open_session(&self, table_id: u32, guests: u32) -> Result<()> {
+        let path = format!("{}/tables/{}/session", self.base_url, table_id);
+        println!("Seating {} guests at table {}", guests, table_id);
+        self.client
+            .post(&path)
+            .json(&serde_json::json!({ "guests": guests }))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn close_session(&self, table_id: u32) -> Result<()> {
+        let path = format!("{}/tables/{}/session", self.base_url, table_id);
+        println!("Guests leaving table {}", table_id);
+        self.client
+            .delete(&path)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn delete_order(&self, table_id: u32, order_id: Uuid) -> Result<()> {
         let path = format!("{}/tables/{}/orders/{}", self.base_url, table_id, order_id);
         println!("Deleting order {} for table {}", order_id, table_id);
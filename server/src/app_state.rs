@@ -1,19 +1,115 @@
-use crate::order::model::Order;
-use std::collections::HashMap;
+use crate::config::model::StorageBackend;
+use crate::internal_store::default_menus;
+use crate::menu::model::Menu;
+use crate::menu::repository::{
+    seed_if_empty, InMemoryMenuRepository, MenuRepository, SqliteMenuRepository,
+};
+use crate::order::estimator::{CookingTimeEstimator, RandomCookingTime};
+use crate::order::repository::{
+    InMemoryOrderRepository, JournaledOrderRepository, OrderRepository, RepositoryError,
+    SqliteOrderRepository,
+};
+use crate::order::ticket::TicketCounter;
+use crate::session::gate::SeatingGate;
+use crate::session::repository::{
+    InMemorySessionRepository, SessionRepository, SqliteSessionRepository,
+};
+use crate::storage::SqliteDatabase;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
 
-pub type OrderStorage = Arc<RwLock<HashMap<u32, Vec<Order>>>>;
+pub type OrderStorage = Arc<dyn OrderRepository>;
+pub type MenuStorage = Arc<dyn MenuRepository>;
+pub type SessionStorage = Arc<dyn SessionRepository>;
 
 #[derive(Clone)]
 pub struct AppState {
     pub orders: OrderStorage,
+    pub menus: MenuStorage,
+    pub estimator: Arc<dyn CookingTimeEstimator>,
+    pub sessions: SessionStorage,
+    pub tickets: Arc<TicketCounter>,
+    pub seating: Arc<SeatingGate>,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        Self::with_order_storage(Arc::new(InMemoryOrderRepository::new()))
+    }
+
+    pub fn with_order_storage(orders: OrderStorage) -> Self {
         Self {
-            orders: Arc::new(RwLock::new(HashMap::new())),
+            orders,
+            menus: Arc::new(InMemoryMenuRepository::new(default_menus())),
+            estimator: Arc::new(RandomCookingTime),
+            sessions: Arc::new(InMemorySessionRepository::new(Vec::new())),
+            tickets: Arc::new(TicketCounter::default()),
+            seating: Arc::new(SeatingGate::default()),
+        }
+    }
+
+    pub fn with_menu_storage(mut self, menus: MenuStorage) -> Self {
+        self.menus = menus;
+        self
+    }
+
+    pub fn with_session_storage(mut self, sessions: SessionStorage) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    pub fn with_estimator(mut self, estimator: Arc<dyn CookingTimeEstimator>) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Open the configured backend. Persistent backends run their migrations or
+    /// recovery here, and the journal backend starts its snapshot timer.
+    /// Ticket numbers continue after the newest stored ticket.
+    /// A branch `catalog` becomes the active menu; without one, an empty menu
+    /// store is seeded with the default menus.
+    pub async fn from_backend(
+        backend: &StorageBackend,
+        catalog: Option<Vec<Menu>>,
+    ) -> Result<Self, RepositoryError> {
+        let state = match backend {
+            StorageBackend::Memory => {
+                Self::new().with_menu_storage(Arc::new(InMemoryMenuRepository::new(Vec::new())))
+            }
+            StorageBackend::Sqlite { path } => {
+                let db = SqliteDatabase::open(path)?;
+                Self::with_order_storage(Arc::new(SqliteOrderRepository::new(db.clone())))
+                    .with_menu_storage(Arc::new(SqliteMenuRepository::new(db.clone())))
+                    .with_session_storage(Arc::new(SqliteSessionRepository::new(db)))
+            }
+            StorageBackend::Journal {
+                dir,
+                snapshot_interval_secs,
+            } => {
+                let repository = Arc::new(JournaledOrderRepository::open(dir).await?);
+                Arc::clone(&repository)
+                    .spawn_snapshots(Duration::from_secs(*snapshot_interval_secs));
+                let menus =
+                    InMemoryMenuRepository::open_file(Path::new(dir).join("menus.json")).await?;
+                let sessions =
+                    InMemorySessionRepository::open_file(Path::new(dir).join("sessions.json"))
+                        .await?;
+                Self::with_order_storage(repository)
+                    .with_menu_storage(Arc::new(menus))
+                    .with_session_storage(Arc::new(sessions))
+            }
+        };
+        match catalog {
+            Some(menus) => state.menus.apply_catalog(menus).await?,
+            None => seed_if_empty(state.menus.as_ref(), default_menus()).await?,
         }
+        let last_ticket = state.orders.latest_ticket().await?;
+        Ok(Self {
+            tickets: Arc::new(TicketCounter::starting_after(
+                last_ticket.map_or(0, |ticket| ticket.number),
+            )),
+            ..state
+        })
     }
 }
@@ -1,6 +1,11 @@
-use crate::config::model::Config;
+use crate::config::model::{
+    parse_utc_offset, Config, EstimatorKind, StorageBackend, StorageOptions,
+};
+use crate::locale::validate_locale;
 use axum::response::IntoResponse;
 use axum::Json;
+use chrono::{DateTime, FixedOffset, NaiveTime, Utc};
+use std::path::PathBuf;
 use tracing::debug;
 
 pub fn get_config_internally() -> Config {
@@ -9,6 +14,55 @@ pub fn get_config_internally() -> Config {
     config.expect("Config cannot load properly")
 }
 
+pub fn get_storage_backend_internally() -> StorageBackend {
+    let kind = std::env::var("ORDER_STORAGE").unwrap_or("memory".to_string());
+    let defaults = StorageOptions::default();
+    let options = StorageOptions {
+        sqlite_path: std::env::var("SQLITE_PATH").unwrap_or(defaults.sqlite_path),
+        journal_dir: std::env::var("JOURNAL_DIR").unwrap_or(defaults.journal_dir),
+        snapshot_interval_secs: std::env::var("SNAPSHOT_INTERVAL_SECS")
+            .map(|secs| secs.parse::<u64>().unwrap())
+            .unwrap_or(defaults.snapshot_interval_secs),
+    };
+    let backend = StorageBackend::new(&kind, options);
+    backend.expect("Storage config cannot load properly")
+}
+
+pub fn get_estimator_kind_internally() -> EstimatorKind {
+    let kind = std::env::var("COOKING_TIME_ESTIMATOR").unwrap_or("random".to_string());
+    let stations = std::env::var("KITCHEN_STATIONS").unwrap_or("4".to_string());
+    let estimator = EstimatorKind::new(&kind, stations.parse::<usize>().unwrap());
+    estimator.expect("Estimator config cannot load properly")
+}
+
+pub fn get_store_offset_internally() -> FixedOffset {
+    let offset = std::env::var("STORE_UTC_OFFSET").unwrap_or("+09:00".to_string());
+    parse_utc_offset(&offset).expect("Store UTC offset cannot load properly")
+}
+
+/// Wall-clock time at the store, which is what menu availability hours mean.
+pub fn store_time(now: DateTime<Utc>) -> NaiveTime {
+    now.with_timezone(&get_store_offset_internally()).time()
+}
+
+/// Locale of each menu's own `name`, used when a guest asks for none we have.
+pub fn get_default_locale_internally() -> String {
+    let locale = std::env::var("DEFAULT_LOCALE")
+        .unwrap_or("en".to_string())
+        .trim()
+        .to_ascii_lowercase();
+    validate_locale(&locale).expect("Default locale cannot load properly");
+    locale
+}
+
+/// Branch catalog file, if this deployment ships its own menu.
+pub fn get_menu_catalog_path_internally() -> Option<PathBuf> {
+    std::env::var("MENU_CATALOG_PATH")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+}
+
 pub async fn get_configs() -> impl IntoResponse {
     let config = get_config_internally();
     let json_response = serde_json::json!({
@@ -44,4 +98,80 @@ mod tests {
         let result = Config::new((100, 1));
         assert!(matches!(result, Err(ConfigError::InvalidRange)));
     }
+
+    #[test]
+    fn test_storage_backend_new() {
+        assert_eq!(
+            StorageBackend::new("memory", StorageOptions::default()).unwrap(),
+            StorageBackend::Memory
+        );
+        assert_eq!(
+            StorageBackend::new("SQLite", StorageOptions::default()).unwrap(),
+            StorageBackend::Sqlite {
+                path: "mealdiner.db".to_string()
+            }
+        );
+        assert_eq!(
+            StorageBackend::new("journal", StorageOptions::default()).unwrap(),
+            StorageBackend::Journal {
+                dir: "journal".to_string(),
+                snapshot_interval_secs: 60
+            }
+        );
+    }
+
+    #[test]
+    fn test_storage_backend_invalid_snapshot_interval() {
+        let options = StorageOptions {
+            snapshot_interval_secs: 0,
+            ..StorageOptions::default()
+        };
+        let result = StorageBackend::new("journal", options);
+        assert!(matches!(result, Err(ConfigError::InvalidSnapshotInterval)));
+    }
+
+    #[test]
+    fn test_storage_backend_unknown() {
+        let result = StorageBackend::new("postgres", StorageOptions::default());
+        assert!(matches!(
+            result,
+            Err(ConfigError::UnknownStorageBackend(kind)) if kind == "postgres"
+        ));
+    }
+
+    #[test]
+    fn test_estimator_kind_new() {
+        assert_eq!(
+            EstimatorKind::new("random", 0).unwrap(),
+            EstimatorKind::Random
+        );
+        assert_eq!(
+            EstimatorKind::new("kitchen_load", 6).unwrap(),
+            EstimatorKind::KitchenLoad { stations: 6 }
+        );
+        assert!(matches!(
+            EstimatorKind::new("kitchen_load", 0),
+            Err(ConfigError::InvalidStations)
+        ));
+        assert!(matches!(
+            EstimatorKind::new("oracle", 4),
+            Err(ConfigError::UnknownEstimator(kind)) if kind == "oracle"
+        ));
+    }
+
+    #[test]
+    fn test_parse_utc_offset() {
+        assert_eq!(
+            parse_utc_offset("+09:00").unwrap(),
+            FixedOffset::east_opt(9 * 3600).unwrap()
+        );
+        assert_eq!(
+            parse_utc_offset("-05:30").unwrap(),
+            FixedOffset::west_opt(5 * 3600 + 1800).unwrap()
+        );
+        assert!(matches!(
+            parse_utc_offset("Tokyo"),
+            Err(ConfigError::InvalidUtcOffset(_))
+        ));
+    }
 }
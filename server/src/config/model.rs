@@ -1,3 +1,4 @@
+use chrono::FixedOffset;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -12,6 +13,56 @@ pub enum ConfigError {
     InvalidStart,
     #[error("End must be greater than or equal to start")]
     InvalidRange,
+    #[error("Unknown order storage backend: {0}")]
+    UnknownStorageBackend(String),
+    #[error("Snapshot interval must be greater than 0")]
+    InvalidSnapshotInterval,
+    #[error("Unknown cooking time estimator: {0}")]
+    UnknownEstimator(String),
+    #[error("Kitchen needs at least one station")]
+    InvalidStations,
+    #[error("Invalid UTC offset '{0}', expected e.g. +09:00")]
+    InvalidUtcOffset(String),
+}
+
+/// Store-local time zone as a fixed offset such as `+09:00`. Menu availability
+/// hours are read in this zone.
+pub fn parse_utc_offset(offset: &str) -> Result<FixedOffset, ConfigError> {
+    offset
+        .trim()
+        .parse::<FixedOffset>()
+        .map_err(|_| ConfigError::InvalidUtcOffset(offset.to_string()))
+}
+
+/// How cooking times and `ready_at` are estimated for new orders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EstimatorKind {
+    /// Random time from each menu's profile, cooking starts immediately.
+    Random,
+    /// Queue behind the unfinished orders on a fixed number of stations.
+    KitchenLoad { stations: usize },
+}
+
+/// Where orders are kept. Server-side only, so it is not part of the public `Config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageBackend {
+    Memory,
+    Sqlite {
+        path: String,
+    },
+    /// In-memory store made durable by an append-only journal plus periodic snapshots.
+    Journal {
+        dir: String,
+        snapshot_interval_secs: u64,
+    },
+}
+
+/// Backend specific settings. Only the ones matching the chosen backend are used.
+#[derive(Debug, Clone)]
+pub struct StorageOptions {
+    pub sqlite_path: String,
+    pub journal_dir: String,
+    pub snapshot_interval_secs: u64,
 }
 
 impl Config {
@@ -25,3 +76,49 @@ impl Config {
         Ok(Self { table_range })
     }
 }
+
+impl StorageBackend {
+    pub fn new(kind: &str, options: StorageOptions) -> Result<Self, ConfigError> {
+        match kind.to_ascii_lowercase().as_str() {
+            "memory" => Ok(StorageBackend::Memory),
+            "sqlite" => Ok(StorageBackend::Sqlite {
+                path: options.sqlite_path,
+            }),
+            "journal" => {
+                if options.snapshot_interval_secs == 0 {
+                    return Err(ConfigError::InvalidSnapshotInterval);
+                }
+                Ok(StorageBackend::Journal {
+                    dir: options.journal_dir,
+                    snapshot_interval_secs: options.snapshot_interval_secs,
+                })
+            }
+            _ => Err(ConfigError::UnknownStorageBackend(kind.to_string())),
+        }
+    }
+}
+
+impl Default for StorageOptions {
+    fn default() -> Self {
+        Self {
+            sqlite_path: "mealdiner.db".to_string(),
+            journal_dir: "journal".to_string(),
+            snapshot_interval_secs: 60,
+        }
+    }
+}
+
+impl EstimatorKind {
+    pub fn new(kind: &str, stations: usize) -> Result<Self, ConfigError> {
+        match kind.to_ascii_lowercase().as_str() {
+            "random" => Ok(EstimatorKind::Random),
+            "kitchen_load" => {
+                if stations == 0 {
+                    return Err(ConfigError::InvalidStations);
+                }
+                Ok(EstimatorKind::KitchenLoad { stations })
+            }
+            _ => Err(ConfigError::UnknownEstimator(kind.to_string())),
+        }
+    }
+}
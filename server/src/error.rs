@@ -0,0 +1,69 @@
+use crate::order::model::InvalidLine;
+use crate::order::repository::RepositoryError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use thiserror::Error;
+use tracing::error;
+
+/// Error for handlers that need to tell the client *why* a request was refused.
+/// Handlers that only ever fail with a bare status keep returning `StatusCode`.
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("Not found")]
+    NotFound,
+    #[error("{0}")]
+    Conflict(String),
+    #[error("Some order lines are invalid")]
+    InvalidLines(Vec<InvalidLine>),
+    #[error(transparent)]
+    Storage(RepositoryError),
+}
+
+impl From<RepositoryError> for ApiError {
+    fn from(e: RepositoryError) -> Self {
+        match e {
+            RepositoryError::Duplicate(message) => ApiError::Conflict(message),
+            other => ApiError::Storage(other),
+        }
+    }
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::InvalidLines(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let message = match &self {
+            ApiError::Storage(e) => {
+                error!("Storage failure: {}", e);
+                "Internal server error".to_string()
+            }
+            other => other.to_string(),
+        };
+        let body = match &self {
+            ApiError::InvalidLines(errors) => json!({
+                "status": "fail",
+                "message": message,
+                "errors": errors
+            }),
+            _ => json!({
+                "status": "fail",
+                "message": message
+            }),
+        };
+        (self.status(), Json(body)).into_response()
+    }
+}
@@ -1,5 +1,8 @@
+use crate::order::repository::RepositoryError;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
+use tracing::error;
 
 pub async fn health_check_handler() -> impl IntoResponse {
     const MESSAGE: &str = "Healthy 🙇‍♂️";
@@ -12,11 +15,16 @@ pub async fn health_check_handler() -> impl IntoResponse {
     Json(json_response)
 }
 
+/// Storage failures are logged here and never leak their details to the client.
+pub fn internal_error(e: RepositoryError) -> StatusCode {
+    error!("Order storage failure: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use axum::{body::to_bytes, response::Response};
-    use http::StatusCode;
     use serde_json::Value;
 
     #[tokio::test]
@@ -1,11 +1,76 @@
-use crate::menu::model::Menu;
-use once_cell::sync::Lazy;
-use std::sync::Arc;
+use crate::menu::model::{Allergen, CookingTimeProfile, Menu, MenuCategory, Price};
+use crate::menu::modifier::{ModifierGroup, ModifierOption};
+use std::collections::BTreeMap;
 
-pub static MENUS: Lazy<Arc<[Menu]>> = Lazy::new(|| {
-    Arc::new([
-        Menu::new("Ramen"),
-        Menu::new("Beef rice"),
-        Menu::new("Beer"),
-    ])
-});
+/// The catalog a brand new store starts with. Once menus are stored, the
+/// admin endpoints own them and this list is no longer read. Ids come from the
+/// SKUs, so even the in-memory store hands out the same ids after a restart.
+pub fn default_menus() -> Vec<Menu> {
+    vec![
+        Menu::new(
+            "ramen",
+            "Ramen",
+            Price::jpy(980),
+            CookingTimeProfile::triangular(8, 10, 15),
+        )
+        .with_names(japanese("ラーメン"))
+        .with_modifier_groups(ramen_modifiers())
+        .with_allergens(vec![Allergen::Wheat, Allergen::Egg, Allergen::Soy]),
+        Menu::new(
+            "beef-rice",
+            "Beef rice",
+            Price::jpy(750),
+            CookingTimeProfile::uniform(5, 8),
+        )
+        .with_names(japanese("牛丼"))
+        .with_allergens(vec![Allergen::Wheat, Allergen::Soy]),
+        Menu::new(
+            "beer",
+            "Beer",
+            Price::jpy(550),
+            CookingTimeProfile::uniform(1, 2),
+        )
+        .with_names(japanese("ビール"))
+        .with_category(MenuCategory::Drinks)
+        .with_allergens(vec![Allergen::Alcohol]),
+    ]
+}
+
+fn japanese(name: &str) -> BTreeMap<String, String> {
+    BTreeMap::from([("ja".to_string(), name.to_string())])
+}
+
+/// All optional, so a plain "Ramen" order is still valid.
+fn ramen_modifiers() -> Vec<ModifierGroup> {
+    vec![
+        ModifierGroup::optional(
+            "firmness",
+            "Noodle firmness",
+            1,
+            vec![
+                ModifierOption::new("soft", "Soft", 0),
+                ModifierOption::new("firm", "Firm", 0),
+                ModifierOption::new("extra-firm", "Extra firm", 0),
+            ],
+        ),
+        ModifierGroup::optional(
+            "richness",
+            "Soup richness",
+            1,
+            vec![
+                ModifierOption::new("light", "Light", 0),
+                ModifierOption::new("rich", "Rich", 0),
+            ],
+        ),
+        ModifierGroup::optional(
+            "toppings",
+            "Extra toppings",
+            3,
+            vec![
+                ModifierOption::new("chashu", "Extra chashu", 250),
+                ModifierOption::new("egg", "Seasoned egg", 100),
+                ModifierOption::new("nori", "Nori", 50),
+            ],
+        ),
+    ]
+}
@@ -0,0 +1,158 @@
+use crate::config::handler::get_default_locale_internally;
+use async_trait::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::http::request::Parts;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+/// Locale tags are lowercase BCP 47 style: a language, optionally with a region
+/// or script, e.g. `ja`, `en`, `zh-tw`.
+pub fn validate_locale(tag: &str) -> Result<(), String> {
+    let mut parts = tag.split('-');
+    let language = parts.next().unwrap_or_default();
+    let valid_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase());
+    let valid_rest = parts.all(|part| {
+        (2..=8).contains(&part.len())
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    });
+    if valid_language && valid_rest {
+        Ok(())
+    } else {
+        Err(format!(
+            "Locale '{}' must look like 'ja', 'en' or 'zh-tw'",
+            tag
+        ))
+    }
+}
+
+/// Tags of an `Accept-Language` header, most preferred first. Entries with
+/// `q=0` and the `*` wildcard are dropped; malformed entries are ignored.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (tag != "*" && quality > 0.0 && validate_locale(&tag).is_ok()).then_some((tag, quality))
+        })
+        .collect();
+    // Stable, so equally weighted tags keep the client's order.
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Locales a response should be written in, best first. `?lang=` beats the
+/// `Accept-Language` header, and the store's default locale comes last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreferredLocales {
+    requested: Vec<String>,
+    /// The locale menus' own `name` is written in.
+    default: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LangQuery {
+    lang: Option<String>,
+}
+
+impl PreferredLocales {
+    pub fn from_request(lang: Option<&str>, accept_language: Option<&str>) -> Self {
+        let mut requested = Vec::new();
+        if let Some(lang) = lang.map(|lang| lang.trim().to_ascii_lowercase()) {
+            if validate_locale(&lang).is_ok() {
+                requested.push(lang);
+            }
+        }
+        requested.extend(
+            accept_language
+                .map(parse_accept_language)
+                .unwrap_or_default(),
+        );
+        Self {
+            requested,
+            default: get_default_locale_internally(),
+        }
+    }
+
+    /// The best of `names` for these locales; `ja-jp` also matches a plain `ja`.
+    /// `fallback` is the name in the default locale, used when that locale is
+    /// asked for or nothing better exists.
+    pub fn pick<'a>(&self, names: &'a BTreeMap<String, String>, fallback: &'a str) -> &'a str {
+        for tag in self.requested.iter().chain(std::iter::once(&self.default)) {
+            let language = tag.split('-').next().unwrap_or(tag);
+            if let Some(name) = names.get(tag).or_else(|| names.get(language)) {
+                return name;
+            }
+            if tag == &self.default || language == self.default {
+                return fallback;
+            }
+        }
+        fallback
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for PreferredLocales {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // A malformed query string just means no `?lang=`.
+        let lang = Query::<LangQuery>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .and_then(|Query(query)| query.lang);
+        let accept_language = parts
+            .headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+        Ok(Self::from_request(lang.as_deref(), accept_language))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language_is_ordered_by_quality() {
+        assert_eq!(
+            parse_accept_language("en;q=0.5, ja-JP, fr;q=0, *;q=0.1, ja;q=0.9"),
+            ["ja-jp", "ja", "en"]
+        );
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn test_pick_falls_back_to_default_and_catalog_name() {
+        let names = BTreeMap::from([
+            ("ja".to_string(), "ラーメン".to_string()),
+            ("en".to_string(), "Ramen".to_string()),
+        ]);
+        let locales = PreferredLocales::from_request(Some("EN"), Some("ja"));
+        assert_eq!(locales.pick(&names, "Ramen (catalog)"), "Ramen");
+        let locales = PreferredLocales::from_request(None, Some("fr, ja-JP;q=0.8"));
+        assert_eq!(locales.pick(&names, "Ramen (catalog)"), "ラーメン");
+        let locales = PreferredLocales::from_request(Some("fr"), None);
+        assert_eq!(locales.pick(&names, "Ramen (catalog)"), "Ramen");
+        let japanese_only = BTreeMap::from([("ja".to_string(), "ラーメン".to_string())]);
+        let locales = PreferredLocales::from_request(Some("en-GB"), Some("ja"));
+        assert_eq!(locales.pick(&japanese_only, "Ramen"), "Ramen");
+    }
+
+    #[test]
+    fn test_validate_locale() {
+        assert!(validate_locale("ja").is_ok());
+        assert!(validate_locale("zh-tw").is_ok());
+        assert!(validate_locale("JA").is_err());
+        assert!(validate_locale("english").is_err());
+        assert!(validate_locale("").is_err());
+    }
+}
@@ -1,19 +1,37 @@
 mod app_state;
 mod config;
+mod error;
 mod handler;
 mod internal_store;
+mod locale;
 mod menu;
 mod order;
+mod session;
+mod storage;
 mod table;
 
 use crate::app_state::AppState;
-use crate::config::handler::get_configs;
+use crate::config::handler::{
+    get_configs, get_default_locale_internally, get_estimator_kind_internally,
+    get_menu_catalog_path_internally, get_storage_backend_internally, get_store_offset_internally,
+};
 use crate::handler::health_check_handler;
-use crate::menu::handler::get_available_menus;
-use crate::order::handler::create_orders;
-use crate::table::handler::{delete_table_order, get_table_order, get_table_orders};
+use crate::menu::catalog::{load_catalog, spawn_catalog_watcher, CATALOG_CHECK_INTERVAL};
+use crate::menu::handler::{
+    create_menu, get_available_menus, list_menus, mark_menu_sold_out, retire_menu, update_menu,
+    update_menu_stock,
+};
+use crate::order::countdown::{spawn_ready_watcher, READY_CHECK_INTERVAL};
+use crate::order::estimator::build_estimator;
+use crate::order::handler::{create_orders, delete_order, get_order, get_ticket};
+use crate::session::handler::{close_table_session, get_table_session, open_table_session};
+use crate::table::handler::{
+    amend_table_order, clear_table_orders, declare_table_allergens, delete_table_order,
+    get_archived_table_orders, get_table_allergens, get_table_order, get_table_orders,
+    get_table_tickets, update_table_order_status,
+};
 use axum::http::{HeaderValue, Method};
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::Router;
 use std::sync::Arc;
 use tower_http::compression::CompressionLayer;
@@ -34,21 +52,81 @@ async fn main() {
 
     let cors = CorsLayer::new()
         .allow_origin("*".parse::<HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::DELETE]);
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ]);
 
-    let app_state = Arc::new(AppState::new());
+    let catalog_path = get_menu_catalog_path_internally();
+    let catalog = match &catalog_path {
+        Some(path) => Some(
+            load_catalog(path)
+                .await
+                .expect("Menu catalog cannot load properly"),
+        ),
+        None => None,
+    };
+    let storage_backend = get_storage_backend_internally();
+    let app_state = AppState::from_backend(&storage_backend, catalog)
+        .await
+        .expect("Order storage cannot open");
+    info!("Order storage: {:?}", storage_backend);
+    if let Some(path) = catalog_path {
+        info!("Menu catalog: {}", path.display());
+        spawn_catalog_watcher(app_state.menus.clone(), path, CATALOG_CHECK_INTERVAL);
+    }
+    let estimator_kind = get_estimator_kind_internally();
+    let estimator = build_estimator(&estimator_kind, app_state.orders.clone());
+    let app_state = Arc::new(app_state.with_estimator(estimator));
+    info!("Cooking time estimator: {:?}", estimator_kind);
+    info!("Store time zone: UTC{}", get_store_offset_internally());
+    info!("Default locale: {}", get_default_locale_internally());
+    spawn_ready_watcher(app_state.clone(), READY_CHECK_INTERVAL);
 
     let table_routes = Router::new()
-        .route("/:id/orders", get(get_table_orders))
+        .route(
+            "/:id/orders",
+            get(get_table_orders).delete(clear_table_orders),
+        )
+        .route("/:id/archived-orders", get(get_archived_table_orders))
+        .route(
+            "/:id/session",
+            get(get_table_session)
+                .post(open_table_session)
+                .delete(close_table_session),
+        )
+        .route("/:id/tickets", get(get_table_tickets))
+        .route(
+            "/:id/allergens",
+            get(get_table_allergens).put(declare_table_allergens),
+        )
         .route("/:id/orders/:order_id", get(get_table_order))
-        .route("/:id/orders/:order_id", delete(delete_table_order));
+        .route("/:id/orders/:order_id", patch(amend_table_order))
+        .route("/:id/orders/:order_id", delete(delete_table_order))
+        .route(
+            "/:id/orders/:order_id/status",
+            patch(update_table_order_status),
+        );
+
+    // Back office only; keep this prefix behind the store's admin network.
+    let admin_routes = Router::new()
+        .route("/menus", get(list_menus).post(create_menu))
+        .route("/menus/:menu_id", put(update_menu).delete(retire_menu));
 
     let app = Router::new()
         .route("/health", get(health_check_handler))
         .route("/configs", get(get_configs))
         .route("/menus", get(get_available_menus))
+        .route("/menus/:menu_id/stock", put(update_menu_stock))
+        .route("/menus/:menu_id/sold-out", post(mark_menu_sold_out))
         .route("/orders", post(create_orders))
+        .route("/orders/:order_id", get(get_order).delete(delete_order))
+        .route("/tickets/:ticket_id", get(get_ticket))
         .nest("/tables", table_routes)
+        .nest("/admin", admin_routes)
         .layer(TraceLayer::new_for_http())
         .layer(CompressionLayer::new())
         .layer(cors)
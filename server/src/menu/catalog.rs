@@ -0,0 +1,322 @@
+use crate::app_state::MenuStorage;
+use crate::menu::combo::{check_combos, ComboComponent};
+use crate::menu::model::{
+    check_currencies, menu_id_for_sku, Allergen, AvailabilityWindow, CookingTimeProfile,
+    DietaryTag, Menu, MenuCategory, Price,
+};
+use crate::menu::modifier::ModifierGroup;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+pub const CATALOG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Error, Debug)]
+pub enum CatalogError {
+    #[error("Cannot read menu catalog: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("Cannot parse menu catalog: {0}")]
+    Parse(String),
+    #[error("Invalid menu catalog: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+/// On-disk shape of a branch catalog, in TOML or JSON.
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    menus: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogEntry {
+    /// Pins an id handed out before the menu had a SKU. Normally left out.
+    #[serde(default)]
+    id: Option<Uuid>,
+    /// Only a menu with a pinned id may leave it out.
+    #[serde(default)]
+    sku: String,
+    name: String,
+    #[serde(default)]
+    names: BTreeMap<String, String>,
+    #[serde(default)]
+    category: MenuCategory,
+    price: Price,
+    /// Combos may leave it out; their components carry the cooking times.
+    #[serde(default)]
+    cooking_time: CookingTimeProfile,
+    #[serde(default)]
+    availability: Vec<AvailabilityWindow>,
+    #[serde(default)]
+    modifier_groups: Vec<ModifierGroup>,
+    #[serde(default)]
+    allergens: Vec<Allergen>,
+    #[serde(default)]
+    dietary: Vec<DietaryTag>,
+    #[serde(default)]
+    components: Vec<ComboComponent>,
+}
+
+impl From<CatalogEntry> for Menu {
+    fn from(entry: CatalogEntry) -> Self {
+        Menu {
+            id: entry.id.unwrap_or_else(|| menu_id_for_sku(&entry.sku)),
+            sku: entry.sku,
+            name: entry.name.trim().to_string(),
+            names: entry.names,
+            category: entry.category,
+            price: entry.price,
+            cooking_time: entry.cooking_time,
+            availability: entry.availability,
+            modifier_groups: entry.modifier_groups,
+            allergens: entry.allergens,
+            dietary: entry.dietary,
+            components: entry.components,
+            retired: false,
+        }
+    }
+}
+
+/// Parse by file extension: `.json` is JSON, anything else is TOML.
+pub fn parse_catalog(path: &Path, contents: &str) -> Result<Vec<Menu>, CatalogError> {
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let file: CatalogFile = if is_json {
+        serde_json::from_str(contents).map_err(|e| CatalogError::Parse(e.to_string()))?
+    } else {
+        toml::from_str(contents).map_err(|e| CatalogError::Parse(e.to_string()))?
+    };
+    let mut problems: Vec<String> = file
+        .menus
+        .iter()
+        .filter(|entry| entry.sku.is_empty() && entry.id.is_none())
+        .map(|entry| format!("{}: needs a SKU or a pinned id", entry.name))
+        .collect();
+    let menus: Vec<Menu> = file.menus.into_iter().map(Menu::from).collect();
+    if let Err(CatalogError::Invalid(more)) = validate_catalog(&menus) {
+        problems.extend(more);
+    }
+    if !problems.is_empty() {
+        return Err(CatalogError::Invalid(problems));
+    }
+    check_combos(&menus).map_err(CatalogError::Invalid)?;
+    Ok(menus)
+}
+
+/// Every problem is reported at once so a broken file can be fixed in one edit.
+pub fn validate_catalog(menus: &[Menu]) -> Result<(), CatalogError> {
+    let mut problems = Vec::new();
+    if menus.is_empty() {
+        problems.push("Catalog has no menus".to_string());
+    }
+    let mut ids = HashSet::new();
+    let mut skus = HashSet::new();
+    let mut names = HashSet::new();
+    if let Err(reason) = check_currencies(menus) {
+        problems.push(reason);
+    }
+    for menu in menus {
+        if let Err(reason) = menu.validate() {
+            problems.push(format!("{}: {}", menu.sku, reason));
+        }
+        if !ids.insert(menu.id) {
+            problems.push(format!("{}: duplicate id {}", menu.sku, menu.id));
+        }
+        // Menus stored before SKUs existed have none to clash.
+        if !menu.sku.is_empty() && !skus.insert(menu.sku.as_str()) {
+            problems.push(format!("{}: duplicate SKU", menu.sku));
+        }
+        if !names.insert(menu.name.to_lowercase()) {
+            problems.push(format!("{}: duplicate name '{}'", menu.sku, menu.name));
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(CatalogError::Invalid(problems))
+    }
+}
+
+pub async fn load_catalog(path: &Path) -> Result<Vec<Menu>, CatalogError> {
+    let contents = tokio::fs::read_to_string(path).await?;
+    parse_catalog(path, &contents)
+}
+
+/// Re-read the catalog file whenever its contents change and apply it when it is
+/// valid. A broken edit is logged and the last good catalog keeps serving.
+pub fn spawn_catalog_watcher(menus: MenuStorage, path: PathBuf, every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        // Start empty so an edit made between startup load and here is not missed;
+        // re-applying an unchanged catalog is harmless.
+        let mut last_seen: Option<String> = None;
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            reload_catalog(&menus, &path, &mut last_seen).await;
+        }
+    })
+}
+
+/// One check of the watcher: apply the file if it changed since `last_seen`.
+async fn reload_catalog(menus: &MenuStorage, path: &Path, last_seen: &mut Option<String>) {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Cannot read menu catalog {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if last_seen.as_deref() == Some(contents.as_str()) {
+        return;
+    }
+    *last_seen = Some(contents.clone());
+    match parse_catalog(path, &contents) {
+        Ok(catalog) => {
+            let count = catalog.len();
+            match menus.apply_catalog(catalog).await {
+                Ok(()) => info!("Reloaded {} menus from {}", count, path.display()),
+                Err(e) => error!("Failed to apply menu catalog: {}", e),
+            }
+        }
+        Err(e) => warn!("{}; keeping the last good catalog", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::combo::with_component_allergens;
+    use crate::menu::repository::InMemoryMenuRepository;
+    use std::sync::Arc;
+
+    const EXAMPLE: &str = include_str!("../../menus.example.toml");
+
+    #[test]
+    fn test_example_catalog_is_valid() {
+        let menus = parse_catalog(Path::new("menus.example.toml"), EXAMPLE).unwrap();
+        let ramen = menus.iter().find(|menu| menu.sku == "ramen").unwrap();
+        assert_eq!(ramen.id, menu_id_for_sku("ramen"));
+        assert_eq!(ramen.price, Price::jpy(980));
+        let beer = menus.iter().find(|menu| menu.sku == "beer").unwrap();
+        assert_eq!(beer.category, MenuCategory::Drinks);
+        let set = menus.iter().find(|menu| menu.sku == "ramen-beer").unwrap();
+        assert_eq!(set.components.len(), 2);
+        let linked = with_component_allergens(menus.clone());
+        let set = linked.iter().find(|menu| menu.sku == "ramen-beer").unwrap();
+        assert!(set.allergens.contains(&Allergen::Alcohol));
+        let lunch = menus.iter().find(|menu| menu.sku == "lunch-set").unwrap();
+        assert_eq!(lunch.availability_label(), "11:00-14:00");
+        assert_eq!(
+            ramen.cooking_time,
+            CookingTimeProfile::triangular(8, 10, 15)
+        );
+    }
+
+    #[test]
+    fn test_json_catalog_with_pinned_id() {
+        let legacy = Uuid::new_v4();
+        let contents = format!(
+            r#"{{"menus": [{{"id": "{}", "sku": "beer", "name": "Beer",
+                "price": {{"amount": 550, "currency": "JPY"}},
+                "cooking_time": {{"min_minutes": 1, "max_minutes": 2, "distribution": {{"kind": "uniform"}}}}}}]}}"#,
+            legacy
+        );
+        let menus = parse_catalog(Path::new("menus.json"), &contents).unwrap();
+        assert_eq!(menus[0].id, legacy);
+    }
+
+    #[test]
+    fn test_only_pinned_menus_may_skip_the_sku() {
+        let entry = |id: &str| {
+            format!(
+                r#"{{{} "name": "Beer", "price": {{"amount": 550, "currency": "JPY"}}}}"#,
+                id
+            )
+        };
+        let pinned = format!(
+            r#"{{"menus": [{}]}}"#,
+            entry(&format!(r#""id": "{}","#, Uuid::new_v4()))
+        );
+        let menus = parse_catalog(Path::new("menus.json"), &pinned).unwrap();
+        assert_eq!(menus[0].sku, "");
+
+        let unpinned = format!(r#"{{"menus": [{}]}}"#, entry(""));
+        assert!(matches!(
+            parse_catalog(Path::new("menus.json"), &unpinned),
+            Err(CatalogError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_catalog_lists_every_problem() {
+        let contents = r#"
+            [[menus]]
+            sku = "ramen"
+            name = "Ramen"
+            price = { amount = 980, currency = "JPY" }
+            cooking_time = { min_minutes = 15, max_minutes = 8, distribution = { kind = "uniform" } }
+
+            [[menus]]
+            sku = "ramen"
+            name = "RAMEN"
+            price = { amount = 9, currency = "USD" }
+            cooking_time = { min_minutes = 8, max_minutes = 15, distribution = { kind = "uniform" } }
+        "#;
+        match parse_catalog(Path::new("menus.toml"), contents) {
+            Err(CatalogError::Invalid(problems)) => {
+                // Mixed currencies, bad cooking time, duplicate id, SKU and name.
+                assert_eq!(problems.len(), 5, "{:?}", problems);
+            }
+            other => panic!("Expected invalid catalog, got {:?}", other),
+        }
+        assert!(matches!(
+            parse_catalog(Path::new("menus.toml"), "menus = 3"),
+            Err(CatalogError::Parse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_watcher_reloads_valid_edits_only() {
+        let path = std::env::temp_dir().join(format!("mealdiner-catalog-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, EXAMPLE).unwrap();
+        let repository = Arc::new(InMemoryMenuRepository::new(
+            load_catalog(&path).await.unwrap(),
+        ));
+        let menus: MenuStorage = repository.clone();
+        let mut last_seen = None;
+
+        let valid = r#"
+            [[menus]]
+            sku = "gyoza"
+            name = "Gyoza"
+            price = { amount = 450, currency = "JPY" }
+            cooking_time = { min_minutes = 4, max_minutes = 6, distribution = { kind = "uniform" } }
+        "#;
+        std::fs::write(&path, valid).unwrap();
+        reload_catalog(&menus, &path, &mut last_seen).await;
+        assert_eq!(active_names(repository.as_ref()).await, ["Gyoza"]);
+
+        std::fs::write(&path, "[[menus]]\nsku = \"broken\"").unwrap();
+        reload_catalog(&menus, &path, &mut last_seen).await;
+        assert_eq!(active_names(repository.as_ref()).await, ["Gyoza"]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    async fn active_names(repository: &InMemoryMenuRepository) -> Vec<String> {
+        use crate::menu::repository::MenuRepository;
+        repository
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|menu| !menu.retired)
+            .map(|menu| menu.name)
+            .collect()
+    }
+}
@@ -0,0 +1,189 @@
+use crate::menu::model::{Allergen, Menu};
+use serde::{Deserialize, Serialize};
+
+/// Most portions of one component a set can hold, to keep typos like 100 beers out.
+pub const MAX_COMPONENT_QUANTITY: u32 = 10;
+
+/// One menu inside a set meal, referenced by SKU so it survives id pinning.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComboComponent {
+    pub sku: String,
+    #[serde(default = "one")]
+    pub quantity: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl ComboComponent {
+    #[cfg(test)]
+    pub fn new(sku: &str, quantity: u32) -> Self {
+        Self {
+            sku: sku.to_string(),
+            quantity,
+        }
+    }
+}
+
+/// Rules a set meal follows on its own, before its components are looked up.
+pub fn validate_components(menu: &Menu) -> Result<(), String> {
+    if !menu.is_combo() {
+        return Ok(());
+    }
+    if !menu.modifier_groups.is_empty() {
+        return Err("A combo cannot have modifier groups of its own".to_string());
+    }
+    for component in &menu.components {
+        if component.sku == menu.sku {
+            return Err("A combo cannot contain itself".to_string());
+        }
+        if !(1..=MAX_COMPONENT_QUANTITY).contains(&component.quantity) {
+            return Err(format!(
+                "Component '{}' must have a quantity of 1-{}",
+                component.sku, MAX_COMPONENT_QUANTITY
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The active menus a combo expands into, one entry per portion. Components must
+/// be plain menus; sets of sets are not supported.
+pub fn expand_combo<'a>(combo: &Menu, catalog: &'a [Menu]) -> Result<Vec<&'a Menu>, String> {
+    let mut portions = Vec::new();
+    for component in &combo.components {
+        let menu = catalog
+            .iter()
+            .find(|menu| menu.sku == component.sku && !menu.retired)
+            .ok_or_else(|| format!("Component '{}' is not an active menu", component.sku))?;
+        if menu.is_combo() {
+            return Err(format!("Component '{}' is itself a combo", component.sku));
+        }
+        portions.extend(std::iter::repeat_n(menu, component.quantity as usize));
+    }
+    Ok(portions)
+}
+
+/// A combo contains whatever its own entry lists plus everything in its portions.
+pub fn combo_allergens(combo: &Menu, portions: &[&Menu]) -> Vec<Allergen> {
+    let mut found = combo.allergens.clone();
+    found.extend(portions.iter().flat_map(|p| p.allergens.iter().copied()));
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// Expand a combo and check its dietary tags against what its portions contain,
+/// so a vegan set cannot hold an egg dish.
+pub fn link_combo<'a>(combo: &Menu, catalog: &'a [Menu]) -> Result<Vec<&'a Menu>, String> {
+    let portions = expand_combo(combo, catalog)?;
+    combo.check_dietary(&combo_allergens(combo, &portions))?;
+    Ok(portions)
+}
+
+/// Link every active combo of `catalog`, collecting every problem.
+pub fn check_combos(catalog: &[Menu]) -> Result<(), Vec<String>> {
+    let problems: Vec<String> = catalog
+        .iter()
+        .filter(|menu| menu.is_combo() && !menu.retired)
+        .filter_map(|menu| {
+            let reason = link_combo(menu, catalog).err()?;
+            Some(format!("{}: {}", menu.sku, reason))
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// `catalog` with every combo listing its current components' allergens, so the
+/// allergen filter also hides the set. Computed on every read: a component that
+/// changes or retires must not leave a stale list behind. A combo whose
+/// components are gone keeps its own list; it cannot be ordered anyway.
+pub fn with_component_allergens(mut catalog: Vec<Menu>) -> Vec<Menu> {
+    let found: Vec<Option<Vec<Allergen>>> = catalog
+        .iter()
+        .map(|menu| {
+            if !menu.is_combo() {
+                return None;
+            }
+            let portions = expand_combo(menu, &catalog).ok()?;
+            Some(combo_allergens(menu, &portions))
+        })
+        .collect();
+    for (menu, found) in catalog.iter_mut().zip(found) {
+        if let Some(found) = found {
+            menu.allergens = found;
+        }
+    }
+    catalog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::model::{Allergen, CookingTimeProfile, DietaryTag, Price};
+
+    fn menu(sku: &str, allergens: Vec<Allergen>) -> Menu {
+        Menu::new(sku, sku, Price::jpy(500), CookingTimeProfile::default())
+            .with_allergens(allergens)
+    }
+
+    #[test]
+    fn test_combo_expands_into_portions() {
+        let catalog = vec![
+            menu("ramen", vec![Allergen::Wheat]),
+            menu("beer", vec![Allergen::Alcohol]),
+            menu("ramen-beer", vec![]).with_components(vec![
+                ComboComponent::new("ramen", 1),
+                ComboComponent::new("beer", 2),
+            ]),
+        ];
+        check_combos(&catalog).unwrap();
+        // Stored combos keep only their own list; readers get the components' too.
+        assert!(catalog[2].allergens.is_empty());
+        let catalog = with_component_allergens(catalog);
+        assert_eq!(catalog[2].allergens, [Allergen::Alcohol, Allergen::Wheat]);
+
+        let skus: Vec<&str> = expand_combo(&catalog[2], &catalog)
+            .unwrap()
+            .iter()
+            .map(|menu| menu.sku.as_str())
+            .collect();
+        assert_eq!(skus, ["ramen", "beer", "beer"]);
+    }
+
+    #[test]
+    fn test_combo_components_must_be_active_plain_menus() {
+        let mut retired = menu("beer", vec![]);
+        retired.retired = true;
+        let catalog = vec![
+            menu("ramen", vec![]),
+            retired,
+            menu("ramen-beer", vec![]).with_components(vec![
+                ComboComponent::new("ramen", 1),
+                ComboComponent::new("beer", 1),
+            ]),
+            menu("double-set", vec![]).with_components(vec![ComboComponent::new("ramen-beer", 2)]),
+        ];
+        let problems = check_combos(&catalog).unwrap_err();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("not an active menu"));
+        assert!(problems[1].contains("itself a combo"));
+
+        let catalog = vec![
+            menu("tamago", vec![Allergen::Egg]),
+            menu("vegan-set", vec![])
+                .with_dietary(vec![DietaryTag::Vegan])
+                .with_components(vec![ComboComponent::new("tamago", 1)]),
+        ];
+        let problems = check_combos(&catalog).unwrap_err();
+        assert!(problems[0].contains("vegan"), "{:?}", problems);
+
+        let looping = menu("loop", vec![]).with_components(vec![ComboComponent::new("loop", 1)]);
+        assert!(validate_components(&looping).is_err());
+    }
+}
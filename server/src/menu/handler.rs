@@ -1,38 +1,275 @@
-use crate::internal_store::MENUS;
+use crate::app_state::AppState;
+use crate::config::handler::store_time;
+use crate::error::ApiError;
+use crate::locale::PreferredLocales;
+use crate::menu::combo::{combo_allergens, expand_combo, link_combo, with_component_allergens};
+use crate::menu::model::{
+    check_currencies, sku_from_name, validate_sku, Menu, MenuQuery, MenuRequest, MenuResponse,
+    StockRequest,
+};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
-use std::ops::Deref;
+use chrono::Utc;
+use serde_json::json;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::info;
+use uuid::Uuid;
 
-pub async fn get_available_menus() -> impl IntoResponse {
-    let menus = Arc::clone(&MENUS);
-    let data = menus.deref();
-    let json_response = serde_json::json!({
+/// What the tablets show: every menu that can be ordered right now, optionally
+/// narrowed to one category and without the allergens a guest avoids. Names are
+/// in the guest's language when the catalog has it. Sold-out menus stay listed so tablets can grey them out.
+pub async fn get_available_menus(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Query(query): Query<MenuQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let now = store_time(Utc::now());
+    let stock = state.menus.stock_levels().await?;
+    let catalog = with_component_allergens(state.menus.list().await?);
+    // A set is only on sale while every one of its portions is.
+    let orderable = |menu: &Menu| {
+        !menu.retired
+            && menu.is_available_at(now)
+            && expand_combo(menu, &catalog)
+                .is_ok_and(|portions| portions.iter().all(|p| p.is_available_at(now)))
+    };
+    let menus: Vec<MenuResponse> = catalog
+        .iter()
+        .filter(|menu| orderable(menu))
+        .filter(|menu| {
+            query
+                .category
+                .is_none_or(|category| menu.category == category)
+        })
+        .filter(|menu| menu.allergens_among(&query.exclude_allergens).is_empty())
+        .map(|menu| MenuResponse::new(menu.clone().localized(&locales), &stock))
+        .collect();
+    let json_response = json!({
         "status": "success",
-        "data": *data
+        "data": menus
     });
     info!("Available menus: {:?}", json_response);
-    Json(json_response)
+    Ok(Json(json_response))
+}
+
+/// Admin view of the catalog, retired menus included.
+pub async fn list_menus(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, ApiError> {
+    let stock = state.menus.stock_levels().await?;
+    let menus: Vec<MenuResponse> = with_component_allergens(state.menus.list().await?)
+        .into_iter()
+        .map(|menu| MenuResponse::new(menu, &stock))
+        .collect();
+    Ok(Json(json!({ "status": "success", "data": menus })))
+}
+
+pub async fn create_menu(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MenuRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sku = match payload.sku {
+        Some(sku) => {
+            let sku = sku.trim().to_string();
+            validate_sku(&sku).map_err(ApiError::BadRequest)?;
+            sku
+        }
+        None => {
+            let stored = state.menus.list().await?;
+            let taken: HashSet<&str> = stored.iter().map(|menu| menu.sku.as_str()).collect();
+            sku_from_name(&payload.name, &taken)
+        }
+    };
+    let menu = Menu::new_admin(
+        &sku,
+        payload.name.trim(),
+        payload.price,
+        payload.cooking_time,
+    )
+    .with_names(payload.names)
+    .with_category(payload.category)
+    .with_availability(payload.availability)
+    .with_modifier_groups(payload.modifier_groups)
+    .with_allergens(payload.allergens)
+    .with_dietary(payload.dietary)
+    .with_components(payload.components);
+    menu.validate().map_err(ApiError::BadRequest)?;
+    let catalog = state.menus.list().await?;
+    check_branch_currency(&catalog, &menu)?;
+    let linked = check_links(&catalog, &menu)?;
+    state.menus.create(menu.clone()).await?;
+    info!("Created menu {} ({})", menu.name, menu.id);
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "status": "success", "data": linked })),
+    ))
+}
+
+/// Rename a menu or change its cooking time. Its id stays the same, so orders
+/// already placed keep pointing at it.
+pub async fn update_menu(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<Uuid>,
+    Json(payload): Json<MenuRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Some(current) = state.menus.get(menu_id).await? else {
+        return Err(ApiError::NotFound);
+    };
+    if current.retired {
+        return Err(ApiError::Conflict(format!(
+            "Menu '{}' is retired and can no longer change",
+            current.name
+        )));
+    }
+    // Orders keep the price they were placed at, so repricing is safe.
+    let menu = Menu {
+        name: payload.name.trim().to_string(),
+        names: payload.names,
+        category: payload.category,
+        price: payload.price,
+        cooking_time: payload.cooking_time,
+        availability: payload.availability,
+        modifier_groups: payload.modifier_groups,
+        allergens: payload.allergens,
+        dietary: payload.dietary,
+        components: payload.components,
+        ..current
+    };
+    menu.validate().map_err(ApiError::BadRequest)?;
+    let catalog = state.menus.list().await?;
+    check_branch_currency(&catalog, &menu)?;
+    let linked = check_links(&catalog, &menu)?;
+    if !state.menus.update(menu.clone()).await? {
+        // Retired or removed between the read and the write.
+        return Err(ApiError::NotFound);
+    }
+    info!("Updated menu {} ({})", menu.name, menu.id);
+    Ok(Json(json!({ "status": "success", "data": linked })))
+}
+
+/// A menu must be priced in the currency the other active menus use.
+fn check_branch_currency(catalog: &[Menu], menu: &Menu) -> Result<(), ApiError> {
+    let others = catalog
+        .iter()
+        .filter(|stored| !stored.retired && stored.id != menu.id);
+    check_currencies(others.chain([menu])).map_err(ApiError::BadRequest)
+}
+
+/// Check `menu` against the rest of `catalog` as if it were already stored:
+/// a combo must point at active plain menus its dietary tags hold for, and the
+/// sets that contain it must stay valid. Returns `menu` as readers will see it.
+fn check_links(catalog: &[Menu], menu: &Menu) -> Result<Menu, ApiError> {
+    let mut next: Vec<Menu> = catalog
+        .iter()
+        .filter(|stored| stored.id != menu.id)
+        .cloned()
+        .collect();
+    next.push(menu.clone());
+    let mut linked = menu.clone();
+    if menu.is_combo() {
+        let portions = link_combo(menu, &next).map_err(ApiError::BadRequest)?;
+        linked.allergens = combo_allergens(menu, &portions);
+    }
+    let contains_menu =
+        |combo: &Menu| !menu.sku.is_empty() && combo.components.iter().any(|c| c.sku == menu.sku);
+    let affected: Vec<String> = next
+        .iter()
+        .filter(|combo| !combo.retired && contains_menu(combo))
+        .filter_map(|combo| {
+            let reason = link_combo(combo, &next).err()?;
+            Some(format!("Set '{}': {}", combo.name, reason))
+        })
+        .collect();
+    if !affected.is_empty() {
+        return Err(ApiError::BadRequest(affected.join("; ")));
+    }
+    Ok(linked)
+}
+
+/// Menus are never hard-deleted: existing orders still reference them.
+pub async fn retire_menu(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    match state.menus.retire(menu_id).await? {
+        Some(menu) => {
+            info!("Retired menu {} ({})", menu.name, menu.id);
+            Ok(Json(json!({ "status": "success", "data": menu })))
+        }
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Kitchen staff set how many portions are left, e.g. after a delivery.
+pub async fn update_menu_stock(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<Uuid>,
+    Json(payload): Json<StockRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_stock(&state, menu_id, payload.quantity).await
+}
+
+pub async fn mark_menu_sold_out(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_stock(&state, menu_id, Some(0)).await
+}
+
+async fn set_stock(
+    state: &AppState,
+    menu_id: Uuid,
+    quantity: Option<u32>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !state.menus.set_stock(menu_id, quantity).await? {
+        return Err(ApiError::NotFound);
+    }
+    let menu = with_component_allergens(state.menus.list().await?)
+        .into_iter()
+        .find(|menu| menu.id == menu_id)
+        .ok_or(ApiError::NotFound)?;
+    info!("Stock of {} set to {:?}", menu.name, quantity);
+    let data = MenuResponse::new(menu, &state.menus.stock_levels().await?);
+    Ok(Json(json!({ "status": "success", "data": data })))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::menu::combo::ComboComponent;
+    use crate::menu::model::{
+        menu_id_for_sku, Allergen, AvailabilityWindow, CookingTimeProfile, DietaryTag,
+        MenuCategory, Price,
+    };
+    use crate::menu::repository::InMemoryMenuRepository;
     use crate::order::model::MenuData;
     use axum::body::to_bytes;
     use axum::response::Response;
-    use http::StatusCode;
     use serde::Deserialize;
     use serde_json::Value;
-    use uuid::Uuid;
+
+    fn create_test_state() -> Arc<AppState> {
+        Arc::new(AppState::new())
+    }
+
+    async fn read_json(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
 
     #[tokio::test]
     async fn test_get_available_menus() {
-        let response: Response = get_available_menus().await.into_response();
+        let response: Response = get_available_menus(
+            State(create_test_state()),
+            PreferredLocales::default(),
+            Query(MenuQuery::default()),
+        )
+        .await
+        .into_response();
         assert_eq!(response.status(), StatusCode::OK);
 
-        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let json: Value = serde_json::from_slice(&body).unwrap();
 
         assert_eq!(json["status"], "success");
@@ -53,8 +290,14 @@ mod tests {
 
     #[tokio::test]
     async fn test_menu_uniqueness() {
-        let response: Response = get_available_menus().await.into_response();
-        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let response: Response = get_available_menus(
+            State(create_test_state()),
+            PreferredLocales::default(),
+            Query(MenuQuery::default()),
+        )
+        .await
+        .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let json: Value = serde_json::from_slice(&body).unwrap();
 
         let menus = json["data"].as_array().unwrap();
@@ -74,8 +317,14 @@ mod tests {
 
     #[tokio::test]
     async fn test_menu_response_serialization() {
-        let response: Response = get_available_menus().await.into_response();
-        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let response: Response = get_available_menus(
+            State(create_test_state()),
+            PreferredLocales::default(),
+            Query(MenuQuery::default()),
+        )
+        .await
+        .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
 
         #[derive(Debug, Deserialize)]
         struct ApiResponse {
@@ -90,8 +339,14 @@ mod tests {
 
     #[tokio::test]
     async fn test_menu_content_validation() {
-        let response: Response = get_available_menus().await.into_response();
-        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let response: Response = get_available_menus(
+            State(create_test_state()),
+            PreferredLocales::default(),
+            Query(MenuQuery::default()),
+        )
+        .await
+        .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
         let json: Value = serde_json::from_slice(&body).unwrap();
 
         let menus = json["data"].as_array().unwrap();
@@ -105,4 +360,643 @@ mod tests {
             assert!(name.len() <= 50, "Menu name should not be too long");
         }
     }
+
+    #[tokio::test]
+    async fn test_admin_menu_lifecycle() {
+        let state = create_test_state();
+
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                sku: None,
+                name: " Gyoza ".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::uniform(4, 6),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created = read_json(response).await;
+        assert_eq!(created["data"]["name"], "Gyoza");
+        let gyoza_id: Uuid = created["data"]["id"].as_str().unwrap().parse().unwrap();
+
+        let response = update_menu(
+            State(state.clone()),
+            Path(gyoza_id),
+            Json(MenuRequest {
+                sku: None,
+                name: "Pan-fried gyoza".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::uniform(5, 7),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let updated = read_json(response).await;
+        assert_eq!(updated["data"]["id"], gyoza_id.to_string());
+        assert_eq!(updated["data"]["cooking_time"]["max_minutes"], 7);
+
+        let available = read_json(
+            get_available_menus(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Query(MenuQuery::default()),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        assert!(available["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|menu| menu["name"] == "Pan-fried gyoza"));
+
+        let response = retire_menu(State(state.clone()), Path(gyoza_id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let available = read_json(
+            get_available_menus(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Query(MenuQuery::default()),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        assert!(!available["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|menu| menu["id"] == gyoza_id.to_string()));
+
+        let all = read_json(list_menus(State(state.clone())).await.into_response()).await;
+        let retired = all["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|menu| menu["id"] == gyoza_id.to_string())
+            .unwrap();
+        assert_eq!(retired["retired"], true);
+
+        let response = update_menu(
+            State(state),
+            Path(gyoza_id),
+            Json(MenuRequest {
+                sku: None,
+                name: "Gyoza".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::default(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_create_menu_rejects_invalid_and_duplicate() {
+        let state = create_test_state();
+
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                sku: None,
+                name: "rAmEn".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::default(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                sku: None,
+                name: "   ".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::default(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                sku: None,
+                name: "Slow stew".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::uniform(20, 10),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = retire_menu(State(state), Path(Uuid::new_v4()))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_menu_ids_survive_restart() {
+        let before = read_json(
+            get_available_menus(
+                State(create_test_state()),
+                PreferredLocales::default(),
+                Query(MenuQuery::default()),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        // A second state stands in for the server after a restart.
+        let after = read_json(
+            get_available_menus(
+                State(create_test_state()),
+                PreferredLocales::default(),
+                Query(MenuQuery::default()),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(before["data"], after["data"]);
+        assert_eq!(
+            after["data"][0]["id"],
+            menu_id_for_sku(after["data"][0]["sku"].as_str().unwrap()).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_menu_with_explicit_sku() {
+        let state = create_test_state();
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                sku: Some("gyoza-6pc".to_string()),
+                name: "Gyoza".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::default(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        let created = read_json(response).await;
+        assert_eq!(created["data"]["sku"], "gyoza-6pc");
+
+        // SKUs are unique among active menus, like names.
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                sku: Some("gyoza-6pc".to_string()),
+                name: "Six gyoza".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::default(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = create_menu(
+            State(state),
+            Json(MenuRequest {
+                sku: Some("Gyoza 6pc".to_string()),
+                name: "Gyoza six".to_string(),
+                price: Price::jpy(500),
+                cooking_time: CookingTimeProfile::default(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_admin_menus_keep_one_currency() {
+        let state = create_test_state();
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                name: "Gyoza".to_string(),
+                price: Price::new(450, "USD"),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(read_json(response).await["message"]
+            .as_str()
+            .unwrap()
+            .contains("Mixed currencies"));
+
+        let ramen = state.menus.list().await.unwrap()[0].clone();
+        let response = update_menu(
+            State(state),
+            Path(ramen.id),
+            Json(MenuRequest {
+                name: ramen.name.clone(),
+                price: Price::new(9, "USD"),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_retire_then_recreate_same_name() {
+        let state = create_test_state();
+        let gyoza = || MenuRequest {
+            sku: None,
+            name: "Gyoza".to_string(),
+            price: Price::jpy(500),
+            ..Default::default()
+        };
+        let first = read_json(
+            create_menu(State(state.clone()), Json(gyoza()))
+                .await
+                .into_response(),
+        )
+        .await;
+        let first_id: Uuid = first["data"]["id"].as_str().unwrap().parse().unwrap();
+        let response = retire_menu(State(state.clone()), Path(first_id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = create_menu(State(state.clone()), Json(gyoza()))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let second = read_json(response).await;
+        assert_ne!(second["data"]["id"], first["data"]["id"]);
+        assert_eq!(first["data"]["sku"], "gyoza");
+        assert_eq!(second["data"]["sku"], "gyoza-2");
+
+        // Names without any ASCII still get a usable SKU.
+        let response = create_menu(
+            State(state),
+            Json(MenuRequest {
+                name: "餃子".to_string(),
+                ..gyoza()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(read_json(response).await["data"]["sku"], "menu");
+    }
+
+    #[tokio::test]
+    async fn test_update_legacy_menu_without_sku() {
+        // Stored before SKUs existed.
+        let legacy: Menu = serde_json::from_value(json!({
+            "id": Uuid::new_v4(),
+            "name": "Gyoza",
+            "cooking_time": CookingTimeProfile::default(),
+        }))
+        .unwrap();
+        let state = Arc::new(
+            AppState::new()
+                .with_menu_storage(Arc::new(InMemoryMenuRepository::new(vec![legacy.clone()]))),
+        );
+
+        let response = update_menu(
+            State(state),
+            Path(legacy.id),
+            Json(MenuRequest {
+                sku: None,
+                name: "Pan-fried gyoza".to_string(),
+                price: Price::jpy(550),
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let updated = read_json(response).await;
+        assert_eq!(updated["data"]["sku"], "");
+        assert_eq!(updated["data"]["name"], "Pan-fried gyoza");
+    }
+
+    #[tokio::test]
+    async fn test_menus_filter_by_category_and_hours() {
+        let state = create_test_state();
+        let now = store_time(Utc::now());
+        let hour = chrono::Duration::hours(1);
+        let open_now = Menu::new(
+            "edamame",
+            "Edamame",
+            Price::jpy(350),
+            CookingTimeProfile::default(),
+        )
+        .with_category(MenuCategory::Sides)
+        .with_availability(vec![AvailabilityWindow::new(now - hour, now + hour)]);
+        let opens_later = Menu::new(
+            "lunch-set",
+            "Lunch set",
+            Price::jpy(1200),
+            CookingTimeProfile::default(),
+        )
+        .with_availability(vec![AvailabilityWindow::new(now + hour, now + hour * 2)]);
+        state.menus.create(open_now).await.unwrap();
+        state.menus.create(opens_later).await.unwrap();
+
+        let all = read_json(
+            get_available_menus(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Query(MenuQuery::default()),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        let names: Vec<&str> = all["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|menu| menu["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"Edamame"));
+        assert!(!names.contains(&"Lunch set"));
+
+        let drinks = read_json(
+            get_available_menus(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Query(MenuQuery {
+                    category: Some(MenuCategory::Drinks),
+                    ..MenuQuery::default()
+                }),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        let drinks = drinks["data"].as_array().unwrap();
+        assert_eq!(drinks.len(), 1);
+        assert_eq!(drinks[0]["name"], "Beer");
+        assert_eq!(drinks[0]["category"], "drinks");
+    }
+
+    #[tokio::test]
+    async fn test_stock_shows_in_menus() {
+        let state = create_test_state();
+        let menus = state.menus.list().await.unwrap();
+        let beef = menus.iter().find(|menu| menu.name == "Beef rice").unwrap();
+
+        let response = update_menu_stock(
+            State(state.clone()),
+            Path(beef.id),
+            Json(StockRequest { quantity: Some(3) }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let updated = read_json(response).await;
+        assert_eq!(updated["data"]["remaining_stock"], 3);
+        assert_eq!(updated["data"]["sold_out"], false);
+
+        let response = mark_menu_sold_out(State(state.clone()), Path(beef.id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let available = read_json(
+            get_available_menus(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Query(MenuQuery::default()),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        for menu in available["data"].as_array().unwrap() {
+            if menu["name"] == "Beef rice" {
+                assert_eq!(menu["sold_out"], true);
+                assert_eq!(menu["remaining_stock"], 0);
+            } else {
+                assert_eq!(menu["sold_out"], false);
+                assert!(menu["remaining_stock"].is_null());
+            }
+        }
+
+        let response = mark_menu_sold_out(State(state), Path(Uuid::new_v4()))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_menus_exclude_allergens() {
+        let state = create_test_state();
+        let Query(query) = Query::<MenuQuery>::try_from_uri(
+            &"/menus?exclude_allergens=wheat,%20egg".parse().unwrap(),
+        )
+        .unwrap();
+        let menus = read_json(
+            get_available_menus(State(state), PreferredLocales::default(), Query(query))
+                .await
+                .into_response(),
+        )
+        .await;
+        let menus = menus["data"].as_array().unwrap();
+        assert_eq!(menus.len(), 1);
+        assert_eq!(menus[0]["name"], "Beer");
+        assert_eq!(menus[0]["allergens"], json!(["alcohol"]));
+
+        assert!(Query::<MenuQuery>::try_from_uri(
+            &"/menus?exclude_allergens=gluten".parse().unwrap()
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_combo_allergens_follow_current_components() {
+        let state = create_test_state();
+        let set = MenuRequest {
+            name: "Beef rice + Beer".to_string(),
+            price: Price::jpy(1200),
+            components: vec![
+                ComboComponent::new("beef-rice", 1),
+                ComboComponent::new("beer", 1),
+            ],
+            ..Default::default()
+        };
+        let response = create_menu(State(state.clone()), Json(set))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created = read_json(response).await;
+        assert_eq!(
+            created["data"]["allergens"],
+            json!(["alcohol", "soy", "wheat"])
+        );
+        let stored = state.menus.list().await.unwrap().pop().unwrap();
+        assert!(stored.allergens.is_empty());
+
+        let without_egg = || async {
+            let Query(query) =
+                Query::<MenuQuery>::try_from_uri(&"/menus?exclude_allergens=egg".parse().unwrap())
+                    .unwrap();
+            let menus = read_json(
+                get_available_menus(
+                    State(state.clone()),
+                    PreferredLocales::default(),
+                    Query(query),
+                )
+                .await
+                .into_response(),
+            )
+            .await;
+            menus["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|menu| menu["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert!(without_egg()
+            .await
+            .contains(&"Beef rice + Beer".to_string()));
+
+        // The component changes after the set was written.
+        let beef = state.menus.list().await.unwrap()[1].clone();
+        let response = update_menu(
+            State(state.clone()),
+            Path(beef.id),
+            Json(MenuRequest {
+                name: beef.name.clone(),
+                price: beef.price.clone(),
+                allergens: vec![Allergen::Wheat, Allergen::Soy, Allergen::Egg],
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!without_egg()
+            .await
+            .contains(&"Beef rice + Beer".to_string()));
+
+        // Retiring a component takes the set off the tablets too.
+        let beer = state.menus.list().await.unwrap()[2].clone();
+        state.menus.retire(beer.id).await.unwrap();
+        let Query(query) = Query::<MenuQuery>::try_from_uri(&"/menus".parse().unwrap()).unwrap();
+        let menus = read_json(
+            get_available_menus(State(state), PreferredLocales::default(), Query(query))
+                .await
+                .into_response(),
+        )
+        .await;
+        assert!(!menus["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|menu| menu["name"] == "Beef rice + Beer"));
+    }
+
+    #[tokio::test]
+    async fn test_combo_dietary_tags_cover_components() {
+        let state = create_test_state();
+        let vegan_set = || MenuRequest {
+            name: "Vegan set".to_string(),
+            price: Price::jpy(1200),
+            dietary: vec![DietaryTag::Vegan],
+            components: vec![ComboComponent::new("beer", 1)],
+            ..Default::default()
+        };
+        let response = create_menu(State(state.clone()), Json(vegan_set()))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        // Ramen has egg, so it cannot join a vegan set.
+        let response = create_menu(
+            State(state.clone()),
+            Json(MenuRequest {
+                name: "Vegan ramen set".to_string(),
+                components: vec![ComboComponent::new("ramen", 1)],
+                ..vegan_set()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Nor can a component of a vegan set gain egg.
+        let beer = state.menus.list().await.unwrap()[2].clone();
+        let response = update_menu(
+            State(state),
+            Path(beer.id),
+            Json(MenuRequest {
+                name: beer.name.clone(),
+                price: beer.price.clone(),
+                allergens: vec![Allergen::Alcohol, Allergen::Egg],
+                ..Default::default()
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(read_json(response).await["message"]
+            .as_str()
+            .unwrap()
+            .contains("Vegan set"));
+    }
+
+    #[tokio::test]
+    async fn test_menu_names_follow_requested_locale() {
+        let state = create_test_state();
+        let names = |locales: PreferredLocales| {
+            let state = state.clone();
+            async move {
+                let menus = read_json(
+                    get_available_menus(State(state), locales, Query(MenuQuery::default()))
+                        .await
+                        .into_response(),
+                )
+                .await;
+                menus["data"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|menu| menu["name"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let japanese = names(PreferredLocales::from_request(None, Some("ja-JP,en;q=0.5"))).await;
+        assert!(japanese.contains(&"ラーメン".to_string()), "{:?}", japanese);
+        let english = names(PreferredLocales::from_request(Some("en"), Some("ja"))).await;
+        assert!(english.contains(&"Ramen".to_string()), "{:?}", english);
+        // No Korean names, so the default locale's (here the catalog name) is used.
+        let korean = names(PreferredLocales::from_request(Some("ko"), None)).await;
+        assert!(korean.contains(&"Beer".to_string()), "{:?}", korean);
+    }
 }
@@ -1,2 +1,6 @@
+pub mod catalog;
+pub mod combo;
 pub mod handler;
 pub mod model;
+pub mod modifier;
+pub mod repository;
@@ -1,17 +1,696 @@
-use serde::{Deserialize, Serialize};
+use crate::locale::{validate_locale, PreferredLocales};
+use crate::menu::combo::{validate_components, ComboComponent};
+use crate::menu::modifier::{validate_groups, ModifierGroup, SelectedModifier};
+use chrono::NaiveTime;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How long the kitchen usually needs for one item.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CookingTimeProfile {
+    pub min_minutes: u32,
+    pub max_minutes: u32,
+    pub distribution: CookingTimeDistribution,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CookingTimeDistribution {
+    /// Every minute between min and max is equally likely.
+    Uniform,
+    /// Most dishes take about `mode_minutes`, rarely close to min or max.
+    Triangular { mode_minutes: u32 },
+}
+
+/// Namespace for menu ids derived from a SKU. Never change it: every stored order
+/// and every cached tablet menu refers to ids derived from it.
+pub const MENU_ID_NAMESPACE: Uuid = Uuid::from_u128(0x3b0f_6c52_8d4e_4a1f_9c27_5e8a_1d60_b7c4);
+pub const MAX_SKU_LEN: usize = 32;
+/// Longest name a tablet can render on one menu tile.
+pub const MAX_MENU_NAME_LEN: usize = 50;
+/// Nothing on the menu takes longer than this; larger values are typos.
+pub const MAX_COOKING_MINUTES: u32 = 180;
+
+/// Currency of menus stored before prices existed.
+pub const DEFAULT_CURRENCY: &str = "JPY";
+
+/// A price in the currency's minor unit: yen for JPY, cents for USD.
+/// Integers only, so totals never pick up rounding errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Price {
+    pub amount: u64,
+    /// ISO 4217 code such as `JPY`.
+    pub currency: String,
+}
+
+impl Price {
+    pub fn new(amount: u64, currency: &str) -> Self {
+        Self {
+            amount,
+            currency: currency.to_string(),
+        }
+    }
+
+    pub fn jpy(amount: u64) -> Self {
+        Self::new(amount, "JPY")
+    }
+
+    /// This price with modifier deltas added, floored at zero.
+    pub fn with_modifiers(&self, modifiers: &[SelectedModifier]) -> Self {
+        let delta: i64 = modifiers.iter().map(|modifier| modifier.price_delta).sum();
+        Self::new(self.amount.saturating_add_signed(delta), &self.currency)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.currency.len() != 3 || !self.currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(format!(
+                "Currency '{}' must be a three-letter ISO 4217 code",
+                self.currency
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Price {
+    /// Only for records stored before prices existed.
+    fn default() -> Self {
+        Self::new(0, DEFAULT_CURRENCY)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuCategory {
+    Drinks,
+    /// Menus stored before categories existed were all main dishes.
+    #[default]
+    Mains,
+    Sides,
+}
+
+/// Ingredients guests ask to avoid: Japan's labelled allergens plus a few common extras.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Allergen {
+    Alcohol,
+    Buckwheat,
+    Crab,
+    Egg,
+    Fish,
+    Milk,
+    Peanut,
+    Sesame,
+    Shrimp,
+    Soy,
+    Walnut,
+    Wheat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DietaryTag {
+    Vegetarian,
+    Vegan,
+    Halal,
+}
+
+/// Store-local hours a menu can be ordered, `from` inclusive and `until` exclusive.
+/// A window with `until` before `from` runs past midnight, e.g. 22:00-02:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvailabilityWindow {
+    pub from: NaiveTime,
+    pub until: NaiveTime,
+}
+
+impl AvailabilityWindow {
+    #[cfg(test)]
+    pub fn new(from: NaiveTime, until: NaiveTime) -> Self {
+        Self { from, until }
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.until {
+            self.from <= time && time < self.until
+        } else {
+            time >= self.from || time < self.until
+        }
+    }
+}
+
+impl fmt::Display for AvailabilityWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.from.format("%H:%M"),
+            self.until.format("%H:%M")
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Menu {
     pub id: Uuid,
-    pub name: &'static str,
+    /// Stable product code. Catalog menus derive their id from it; menus made
+    /// through the admin API keep the random id they were stored with. Empty for
+    /// menus stored before SKUs existed.
+    #[serde(default)]
+    pub sku: String,
+    /// Name in the store's default locale, also what orders store.
+    pub name: String,
+    /// The name in other locales, keyed by tag such as `ja` or `en`.
+    #[serde(default)]
+    pub names: BTreeMap<String, String>,
+    #[serde(default)]
+    pub category: MenuCategory,
+    #[serde(default)]
+    pub price: Price,
+    pub cooking_time: CookingTimeProfile,
+    /// When the menu can be ordered. Empty means all day.
+    #[serde(default)]
+    pub availability: Vec<AvailabilityWindow>,
+    /// Choices guests make per order, e.g. noodle firmness or extra toppings.
+    #[serde(default)]
+    pub modifier_groups: Vec<ModifierGroup>,
+    /// What the dish contains. Empty means nothing was declared, not "allergen free".
+    /// A set stores only what it adds itself; see `with_component_allergens`.
+    #[serde(default)]
+    pub allergens: Vec<Allergen>,
+    #[serde(default)]
+    pub dietary: Vec<DietaryTag>,
+    /// Set meals list the menus they are made of; the kitchen cooks those instead.
+    #[serde(default)]
+    pub components: Vec<ComboComponent>,
+    /// Retired menus can no longer be ordered, but stay stored so old orders
+    /// still point at something.
+    #[serde(default)]
+    pub retired: bool,
 }
 
 impl Menu {
-    pub fn new(name: &'static str) -> Self {
+    /// The id is derived from `sku`, so the same SKU gets the same id on every boot.
+    /// For built-in and catalog menus; the admin API uses `new_admin`.
+    pub fn new(
+        sku: &str,
+        name: impl Into<String>,
+        price: Price,
+        cooking_time: CookingTimeProfile,
+    ) -> Self {
+        Menu {
+            id: menu_id_for_sku(sku),
+            sku: sku.to_string(),
+            name: name.into(),
+            names: BTreeMap::new(),
+            category: MenuCategory::default(),
+            price,
+            cooking_time,
+            availability: Vec::new(),
+            modifier_groups: Vec::new(),
+            allergens: Vec::new(),
+            dietary: Vec::new(),
+            components: Vec::new(),
+            retired: false,
+        }
+    }
+
+    /// A menu created through the admin API. It gets a random id: it is stored, so
+    /// the id is stable anyway, and a retired menu's SKU can come back as a new menu
+    /// without taking over the old one's id.
+    pub fn new_admin(
+        sku: &str,
+        name: impl Into<String>,
+        price: Price,
+        cooking_time: CookingTimeProfile,
+    ) -> Self {
         Menu {
             id: Uuid::new_v4(),
-            name,
+            ..Self::new(sku, name, price, cooking_time)
+        }
+    }
+
+    pub fn with_names(mut self, names: BTreeMap<String, String>) -> Self {
+        self.names = names;
+        self
+    }
+
+    pub fn with_category(mut self, category: MenuCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub fn with_availability(mut self, availability: Vec<AvailabilityWindow>) -> Self {
+        self.availability = availability;
+        self
+    }
+
+    pub fn with_modifier_groups(mut self, modifier_groups: Vec<ModifierGroup>) -> Self {
+        self.modifier_groups = modifier_groups;
+        self
+    }
+
+    pub fn with_allergens(mut self, allergens: Vec<Allergen>) -> Self {
+        self.allergens = allergens;
+        self
+    }
+
+    pub fn with_dietary(mut self, dietary: Vec<DietaryTag>) -> Self {
+        self.dietary = dietary;
+        self
+    }
+
+    pub fn with_components(mut self, components: Vec<ComboComponent>) -> Self {
+        self.components = components;
+        self
+    }
+
+    /// Whether a guest's `name` refers to this menu in any of its locales.
+    pub fn answers_to(&self, name: &str) -> bool {
+        let name = name.trim();
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .names
+                .values()
+                .any(|localized| localized.trim().eq_ignore_ascii_case(name))
+    }
+
+    /// This menu with `name` in the best locale for the guest.
+    pub fn localized(mut self, locales: &PreferredLocales) -> Self {
+        self.name = locales.pick(&self.names, &self.name).to_string();
+        self
+    }
+
+    pub fn is_combo(&self) -> bool {
+        !self.components.is_empty()
+    }
+
+    /// The allergens out of `avoided` this dish contains.
+    pub fn allergens_among(&self, avoided: &[Allergen]) -> Vec<Allergen> {
+        let mut found: Vec<Allergen> = self
+            .allergens
+            .iter()
+            .copied()
+            .filter(|allergen| avoided.contains(allergen))
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Whether the menu can be ordered at `time`, in store-local time.
+    pub fn is_available_at(&self, time: NaiveTime) -> bool {
+        self.availability.is_empty() || self.availability.iter().any(|window| window.contains(time))
+    }
+
+    /// Human readable hours, e.g. "11:00-14:00, 17:00-21:00".
+    pub fn availability_label(&self) -> String {
+        self.availability
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Checks the SKU's format only when there is one: menus stored before SKUs
+    /// existed must stay editable.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.sku.is_empty() {
+            validate_sku(&self.sku)?;
+        }
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Menu name must not be empty".to_string());
+        }
+        if name.chars().count() > MAX_MENU_NAME_LEN {
+            return Err(format!(
+                "Menu name must be at most {} characters",
+                MAX_MENU_NAME_LEN
+            ));
+        }
+        for (locale, localized) in &self.names {
+            validate_locale(locale)?;
+            let length = localized.trim().chars().count();
+            if length == 0 || length > MAX_MENU_NAME_LEN {
+                return Err(format!(
+                    "Menu name in '{}' must be 1-{} characters",
+                    locale, MAX_MENU_NAME_LEN
+                ));
+            }
+        }
+        self.price.validate()?;
+        if let Some(window) = self.availability.iter().find(|w| w.from == w.until) {
+            return Err(format!(
+                "Availability window {} is empty; leave availability out for all day",
+                window
+            ));
+        }
+        self.check_dietary(&self.allergens)?;
+        validate_groups(&self.modifier_groups)?;
+        validate_components(self)?;
+        self.cooking_time.validate()
+    }
+
+    /// Whether the dietary tags hold for a dish containing `allergens`. Sets are
+    /// checked again with their components' allergens once those are known.
+    pub fn check_dietary(&self, allergens: &[Allergen]) -> Result<(), String> {
+        let plant_based = self
+            .dietary
+            .iter()
+            .any(|tag| matches!(tag, DietaryTag::Vegetarian | DietaryTag::Vegan));
+        if plant_based
+            && allergens
+                .iter()
+                .any(|a| matches!(a, Allergen::Crab | Allergen::Fish | Allergen::Shrimp))
+        {
+            return Err("A vegetarian menu cannot contain fish or shellfish".to_string());
+        }
+        if self.dietary.contains(&DietaryTag::Vegan)
+            && allergens
+                .iter()
+                .any(|a| matches!(a, Allergen::Egg | Allergen::Milk))
+        {
+            return Err("A vegan menu cannot contain egg or milk".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One bill per table, so one currency per branch.
+pub fn check_currencies<'a>(menus: impl IntoIterator<Item = &'a Menu>) -> Result<(), String> {
+    let currencies: HashSet<&str> = menus
+        .into_iter()
+        .map(|menu| menu.price.currency.as_str())
+        .collect();
+    if currencies.len() > 1 {
+        let mut currencies: Vec<&str> = currencies.into_iter().collect();
+        currencies.sort();
+        return Err(format!("Mixed currencies: {}", currencies.join(", ")));
+    }
+    Ok(())
+}
+
+pub fn menu_id_for_sku(sku: &str) -> Uuid {
+    Uuid::new_v5(&MENU_ID_NAMESPACE, sku.as_bytes())
+}
+
+/// SKUs are lowercase ASCII letters, digits, `-` and `_`.
+pub fn validate_sku(sku: &str) -> Result<(), String> {
+    if sku.is_empty() || sku.len() > MAX_SKU_LEN {
+        return Err(format!("SKU must be 1-{} characters", MAX_SKU_LEN));
+    }
+    if !sku
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "SKU '{}' may only contain lowercase letters, digits, '-' and '_'",
+            sku
+        ));
+    }
+    Ok(())
+}
+
+/// SKU used when the admin does not give one: "Beef rice" becomes "beef-rice".
+/// Names without ASCII letters or digits fall back to "menu". A numbered suffix
+/// keeps the SKU clear of `taken` ones, retired menus' included.
+pub fn sku_from_name(name: &str, taken: &HashSet<&str>) -> String {
+    let slug: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let base = if slug.is_empty() {
+        "menu"
+    } else {
+        slug.as_str()
+    };
+    let mut sku: String = base.chars().take(MAX_SKU_LEN).collect();
+    let mut counter = 1;
+    while taken.contains(sku.as_str()) {
+        counter += 1;
+        let suffix = format!("-{}", counter);
+        let stem: String = base.chars().take(MAX_SKU_LEN - suffix.len()).collect();
+        sku = format!("{}{}", stem.trim_end_matches('-'), suffix);
+    }
+    sku
+}
+
+/// What clients see for a menu: the catalog entry plus its live stock.
+#[derive(Debug, Serialize)]
+pub struct MenuResponse {
+    #[serde(flatten)]
+    pub menu: Menu,
+    /// Portions left, or `None` when the kitchen does not count this menu.
+    pub remaining_stock: Option<u32>,
+    pub sold_out: bool,
+}
+
+impl MenuResponse {
+    pub fn new(menu: Menu, stock: &HashMap<Uuid, u32>) -> Self {
+        let remaining_stock = stock.get(&menu.id).copied();
+        Self {
+            sold_out: remaining_stock == Some(0),
+            remaining_stock,
+            menu,
+        }
+    }
+}
+
+/// Body of the staff stock endpoint. `null` stops counting the menu.
+#[derive(Debug, Deserialize)]
+pub struct StockRequest {
+    pub quantity: Option<u32>,
+}
+
+/// Filters of `GET /menus`.
+#[derive(Debug, Default, Deserialize)]
+pub struct MenuQuery {
+    pub category: Option<MenuCategory>,
+    /// `?exclude_allergens=wheat,egg` hides menus containing any of them.
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub exclude_allergens: Vec<Allergen>,
+}
+
+/// Query strings carry lists as `a,b,c`; every item must parse on its own.
+fn comma_separated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let raw = String::deserialize(deserializer)?;
+    raw.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| T::deserialize(IntoDeserializer::<D::Error>::into_deserializer(item)))
+        .collect()
+}
+
+/// Body of the admin create and update endpoints.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MenuRequest {
+    /// Only read on create; a menu's SKU and id never change afterwards.
+    #[serde(default)]
+    pub sku: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub category: MenuCategory,
+    pub price: Price,
+    #[serde(default)]
+    pub cooking_time: CookingTimeProfile,
+    #[serde(default)]
+    pub availability: Vec<AvailabilityWindow>,
+    #[serde(default)]
+    pub modifier_groups: Vec<ModifierGroup>,
+    #[serde(default)]
+    pub allergens: Vec<Allergen>,
+    #[serde(default)]
+    pub dietary: Vec<DietaryTag>,
+    #[serde(default)]
+    pub components: Vec<ComboComponent>,
+    #[serde(default)]
+    pub names: BTreeMap<String, String>,
+}
+
+impl CookingTimeProfile {
+    pub fn uniform(min_minutes: u32, max_minutes: u32) -> Self {
+        Self {
+            min_minutes,
+            max_minutes,
+            distribution: CookingTimeDistribution::Uniform,
+        }
+    }
+
+    pub fn triangular(min_minutes: u32, mode_minutes: u32, max_minutes: u32) -> Self {
+        Self {
+            min_minutes,
+            max_minutes,
+            distribution: CookingTimeDistribution::Triangular { mode_minutes },
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_minutes == 0 {
+            return Err("Cooking time must be at least one minute".to_string());
+        }
+        if self.min_minutes > self.max_minutes {
+            return Err(format!(
+                "Cooking time minimum {} is above maximum {}",
+                self.min_minutes, self.max_minutes
+            ));
+        }
+        if self.max_minutes > MAX_COOKING_MINUTES {
+            return Err(format!(
+                "Cooking time must be at most {} minutes",
+                MAX_COOKING_MINUTES
+            ));
+        }
+        if let CookingTimeDistribution::Triangular { mode_minutes } = self.distribution {
+            if !(self.min_minutes..=self.max_minutes).contains(&mode_minutes) {
+                return Err(format!(
+                    "Most likely cooking time {} is outside {}-{} minutes",
+                    mode_minutes, self.min_minutes, self.max_minutes
+                ));
+            }
         }
+        Ok(())
+    }
+}
+
+impl Default for CookingTimeProfile {
+    /// The original business rule: anything takes 5-15 minutes.
+    fn default() -> Self {
+        Self::uniform(5, 15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_menu_id_is_derived_from_sku() {
+        let first = Menu::new(
+            "ramen",
+            "Ramen",
+            Price::jpy(980),
+            CookingTimeProfile::default(),
+        );
+        let again = Menu::new(
+            "ramen",
+            "Tonkotsu ramen",
+            Price::jpy(1080),
+            CookingTimeProfile::default(),
+        );
+        let other = Menu::new(
+            "beer",
+            "Beer",
+            Price::jpy(550),
+            CookingTimeProfile::default(),
+        );
+        assert_eq!(first.id, again.id);
+        assert_ne!(first.id, other.id);
+        assert_eq!(first.id, menu_id_for_sku("ramen"));
+    }
+
+    #[test]
+    fn test_sku_from_name() {
+        let none = HashSet::new();
+        assert_eq!(sku_from_name("Beef rice", &none), "beef-rice");
+        assert_eq!(sku_from_name("  Fish & Chips! ", &none), "fish-chips");
+        assert_eq!(sku_from_name("ラーメン", &none), "menu");
+        let taken = HashSet::from(["menu", "menu-2", "beef-rice"]);
+        assert_eq!(sku_from_name("ラーメン", &taken), "menu-3");
+        assert_eq!(sku_from_name("Beef rice", &taken), "beef-rice-2");
+        let long = sku_from_name(&"a".repeat(40), &HashSet::from(["a".repeat(32).as_str()]));
+        assert_eq!(long, format!("{}-2", "a".repeat(30)));
+        assert!(validate_sku(&sku_from_name("Beef rice", &none)).is_ok());
+        assert!(validate_sku("Beef rice").is_err());
+        assert!(validate_sku("").is_err());
+    }
+
+    #[test]
+    fn test_price_currency_must_be_iso_code() {
+        assert!(Price::jpy(980).validate().is_ok());
+        assert!(Price::new(350, "USD").validate().is_ok());
+        assert!(Price::new(980, "yen").validate().is_err());
+        assert!(Price::new(980, "JP").validate().is_err());
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_availability_windows() {
+        let lunch = Menu::new(
+            "lunch-set",
+            "Lunch set",
+            Price::jpy(1200),
+            CookingTimeProfile::default(),
+        )
+        .with_availability(vec![AvailabilityWindow::new(at(11, 0), at(14, 0))]);
+        assert!(lunch.is_available_at(at(11, 0)));
+        assert!(lunch.is_available_at(at(13, 59)));
+        assert!(!lunch.is_available_at(at(14, 0)));
+        assert!(!lunch.is_available_at(at(10, 59)));
+        assert_eq!(lunch.availability_label(), "11:00-14:00");
+
+        let late = AvailabilityWindow::new(at(22, 0), at(2, 0));
+        assert!(late.contains(at(23, 30)));
+        assert!(late.contains(at(1, 0)));
+        assert!(!late.contains(at(12, 0)));
+
+        let all_day = Menu::new(
+            "beer",
+            "Beer",
+            Price::jpy(550),
+            CookingTimeProfile::default(),
+        );
+        assert!(all_day.is_available_at(at(4, 0)));
+    }
+
+    #[test]
+    fn test_window_deserializes_from_hours_and_minutes() {
+        let window: AvailabilityWindow =
+            serde_json::from_str(r#"{"from": "11:00", "until": "14:30"}"#).unwrap();
+        assert_eq!(window, AvailabilityWindow::new(at(11, 0), at(14, 30)));
+        let category: MenuCategory = serde_json::from_str(r#""drinks""#).unwrap();
+        assert_eq!(category, MenuCategory::Drinks);
+    }
+
+    #[test]
+    fn test_dietary_tags_must_match_allergens() {
+        let salad = Menu::new(
+            "salad",
+            "Salad",
+            Price::jpy(500),
+            CookingTimeProfile::default(),
+        )
+        .with_dietary(vec![DietaryTag::Vegan]);
+        assert!(salad.validate().is_ok());
+        assert!(salad
+            .clone()
+            .with_allergens(vec![Allergen::Egg])
+            .validate()
+            .is_err());
+        assert!(salad
+            .with_dietary(vec![DietaryTag::Vegetarian])
+            .with_allergens(vec![Allergen::Shrimp])
+            .validate()
+            .is_err());
     }
 }
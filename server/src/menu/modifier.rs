@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A set of choices on a menu, e.g. noodle firmness or toppings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModifierGroup {
+    /// Stable key the tablets send back, unique within the menu.
+    pub id: String,
+    pub name: String,
+    /// A required group needs at least one selection.
+    #[serde(default)]
+    pub required: bool,
+    /// Once anything is picked from an optional group, at least this many.
+    #[serde(default)]
+    pub min_selections: u32,
+    pub max_selections: u32,
+    pub options: Vec<ModifierOption>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModifierOption {
+    /// Stable key, unique within the group.
+    pub id: String,
+    pub name: String,
+    /// Added to the menu price, in the same minor unit. May be negative.
+    #[serde(default)]
+    pub price_delta: i64,
+}
+
+/// One option picked on an order line, as sent by the tablet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModifierChoice {
+    pub group_id: String,
+    pub option_id: String,
+}
+
+/// A picked option as stored on the order. Names and price are copied from the
+/// catalog so later menu edits don't rewrite what was ordered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectedModifier {
+    pub group_id: String,
+    pub group_name: String,
+    pub option_id: String,
+    pub option_name: String,
+    pub price_delta: i64,
+}
+
+impl ModifierOption {
+    pub fn new(id: &str, name: &str, price_delta: i64) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            price_delta,
+        }
+    }
+}
+
+impl ModifierGroup {
+    /// An optional group where guests pick at most `max_selections` options.
+    pub fn optional(
+        id: &str,
+        name: &str,
+        max_selections: u32,
+        options: Vec<ModifierOption>,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            required: false,
+            min_selections: 0,
+            max_selections,
+            options,
+        }
+    }
+
+    /// A group where guests pick exactly one option.
+    #[cfg(test)]
+    pub fn pick_one(id: &str, name: &str, options: Vec<ModifierOption>) -> Self {
+        Self {
+            required: true,
+            min_selections: 1,
+            ..Self::optional(id, name, 1, options)
+        }
+    }
+
+    /// Fewest selections accepted when something is picked (or always, if required).
+    pub fn min_count(&self) -> u32 {
+        if self.required {
+            self.min_selections.max(1)
+        } else {
+            self.min_selections
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() || self.name.trim().is_empty() {
+            return Err("Modifier groups need an id and a name".to_string());
+        }
+        let mut option_ids = HashSet::new();
+        for option in &self.options {
+            if option.id.trim().is_empty() || option.name.trim().is_empty() {
+                return Err(format!(
+                    "Options of modifier group '{}' need an id and a name",
+                    self.id
+                ));
+            }
+            if !option_ids.insert(option.id.as_str()) {
+                return Err(format!(
+                    "Modifier group '{}' has option '{}' twice",
+                    self.id, option.id
+                ));
+            }
+        }
+        let available = u32::try_from(self.options.len()).unwrap_or(u32::MAX);
+        if self.max_selections == 0 || self.max_selections > available {
+            return Err(format!(
+                "Modifier group '{}' must allow 1-{} selections",
+                self.id, available
+            ));
+        }
+        if self.min_count() > self.max_selections {
+            return Err(format!(
+                "Modifier group '{}' needs at least {} but allows at most {} selections",
+                self.id,
+                self.min_count(),
+                self.max_selections
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_groups(groups: &[ModifierGroup]) -> Result<(), String> {
+    let mut group_ids = HashSet::new();
+    for group in groups {
+        group.validate()?;
+        if !group_ids.insert(group.id.as_str()) {
+            return Err(format!("Modifier group '{}' appears twice", group.id));
+        }
+    }
+    Ok(())
+}
+
+/// Check an order line's choices against the menu's groups. Every problem is
+/// reported in one message, since the line is rejected as a whole.
+pub fn select_modifiers(
+    groups: &[ModifierGroup],
+    choices: &[ModifierChoice],
+) -> Result<Vec<SelectedModifier>, String> {
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    for choice in choices {
+        if !seen.insert((choice.group_id.as_str(), choice.option_id.as_str())) {
+            problems.push(format!(
+                "Option '{}' of '{}' is selected twice",
+                choice.option_id, choice.group_id
+            ));
+        }
+        match groups.iter().find(|group| group.id == choice.group_id) {
+            None => problems.push(format!("Unknown modifier group '{}'", choice.group_id)),
+            Some(group) if !group.options.iter().any(|o| o.id == choice.option_id) => problems
+                .push(format!(
+                    "Unknown option '{}' in '{}'",
+                    choice.option_id, group.name
+                )),
+            Some(_) => {}
+        }
+    }
+
+    let mut selected = Vec::new();
+    for group in groups {
+        let picked: Vec<&ModifierOption> = group
+            .options
+            .iter()
+            .filter(|option| {
+                choices
+                    .iter()
+                    .any(|c| c.group_id == group.id && c.option_id == option.id)
+            })
+            .collect();
+        let count = u32::try_from(picked.len()).unwrap_or(u32::MAX);
+        if count == 0 && group.required {
+            problems.push(format!("'{}' is required", group.name));
+        } else if count > 0 && count < group.min_count() {
+            problems.push(format!(
+                "Choose at least {} from '{}'",
+                group.min_count(),
+                group.name
+            ));
+        } else if count > group.max_selections {
+            problems.push(format!(
+                "Choose at most {} from '{}'",
+                group.max_selections, group.name
+            ));
+        }
+        selected.extend(picked.into_iter().map(|option| SelectedModifier {
+            group_id: group.id.clone(),
+            group_name: group.name.clone(),
+            option_id: option.id.clone(),
+            option_name: option.name.clone(),
+            price_delta: option.price_delta,
+        }));
+    }
+
+    if problems.is_empty() {
+        Ok(selected)
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(id: &str, price_delta: i64) -> ModifierOption {
+        ModifierOption::new(id, id, price_delta)
+    }
+
+    fn choice(group_id: &str, option_id: &str) -> ModifierChoice {
+        ModifierChoice {
+            group_id: group_id.to_string(),
+            option_id: option_id.to_string(),
+        }
+    }
+
+    fn ramen_groups() -> Vec<ModifierGroup> {
+        vec![
+            ModifierGroup::pick_one(
+                "firmness",
+                "Noodle firmness",
+                vec![option("soft", 0), option("regular", 0), option("firm", 0)],
+            ),
+            ModifierGroup::optional(
+                "toppings",
+                "Toppings",
+                2,
+                vec![
+                    option("chashu", 250),
+                    option("egg", 100),
+                    option("nori", 50),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_valid_selection_copies_prices() {
+        let groups = ramen_groups();
+        assert!(validate_groups(&groups).is_ok());
+        let selected = select_modifiers(
+            &groups,
+            &[choice("toppings", "chashu"), choice("firmness", "firm")],
+        )
+        .unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].option_id, "firm");
+        assert_eq!(selected[1].group_name, "Toppings");
+        assert_eq!(selected[1].price_delta, 250);
+    }
+
+    #[test]
+    fn test_selection_problems_are_reported_together() {
+        let groups = ramen_groups();
+        let reason = select_modifiers(
+            &groups,
+            &[
+                choice("toppings", "chashu"),
+                choice("toppings", "egg"),
+                choice("toppings", "nori"),
+                choice("soup", "rich"),
+            ],
+        )
+        .unwrap_err();
+        assert!(
+            reason.contains("'Noodle firmness' is required"),
+            "{}",
+            reason
+        );
+        assert!(reason.contains("at most 2"), "{}", reason);
+        assert!(
+            reason.contains("Unknown modifier group 'soup'"),
+            "{}",
+            reason
+        );
+
+        let reason = select_modifiers(
+            &groups,
+            &[choice("firmness", "soft"), choice("firmness", "soft")],
+        )
+        .unwrap_err();
+        assert!(reason.contains("selected twice"), "{}", reason);
+    }
+
+    #[test]
+    fn test_group_validation() {
+        let mut groups = ramen_groups();
+        groups[1].max_selections = 4;
+        assert!(validate_groups(&groups).is_err());
+
+        let mut groups = ramen_groups();
+        groups[0].min_selections = 2;
+        assert!(validate_groups(&groups).is_err());
+
+        let mut groups = ramen_groups();
+        groups[1].id = "firmness".to_string();
+        assert!(validate_groups(&groups).is_err());
+    }
+}
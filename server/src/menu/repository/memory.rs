@@ -0,0 +1,347 @@
+use crate::menu::model::Menu;
+use crate::menu::repository::{
+    duplicate_id, duplicate_name, duplicate_sku, shortages, MenuRepository, ReserveOutcome,
+};
+use crate::storage::file::{read_or_default, write_atomically};
+use crate::storage::RepositoryError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+/// The catalog is a handful of entries, so one list behind one lock is plenty.
+/// With a `file`, every change rewrites it (and stock goes to a sibling
+/// `.stock.json`) so both survive restarts. Locks are always taken menus first.
+pub struct InMemoryMenuRepository {
+    menus: RwLock<Vec<Menu>>,
+    stock: Mutex<HashMap<Uuid, u32>>,
+    file: Option<PathBuf>,
+}
+
+impl InMemoryMenuRepository {
+    pub fn new(menus: Vec<Menu>) -> Self {
+        Self {
+            menus: RwLock::new(menus),
+            stock: Mutex::new(HashMap::new()),
+            file: None,
+        }
+    }
+
+    /// Load the catalog kept at `path`, starting empty when the file does not exist yet.
+    pub async fn open_file(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        let path = path.as_ref().to_path_buf();
+        Ok(Self {
+            menus: RwLock::new(read_or_default(&path).await?),
+            stock: Mutex::new(read_or_default(&stock_path(&path)).await?),
+            file: Some(path),
+        })
+    }
+
+    /// Called with the write lock held so concurrent edits hit the disk in order.
+    async fn persist(&self, menus: &[Menu]) -> Result<(), RepositoryError> {
+        match &self.file {
+            Some(path) => write_atomically(path, menus).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Called with the stock lock held, like `persist`.
+    async fn persist_stock(&self, stock: &HashMap<Uuid, u32>) -> Result<(), RepositoryError> {
+        match &self.file {
+            Some(path) => write_atomically(&stock_path(path), stock).await,
+            None => Ok(()),
+        }
+    }
+}
+
+fn stock_path(path: &Path) -> PathBuf {
+    path.with_extension("stock.json")
+}
+
+fn sku_taken(menus: &[Menu], sku: &str) -> bool {
+    !sku.is_empty() && menus.iter().any(|menu| !menu.retired && menu.sku == sku)
+}
+
+fn name_taken(menus: &[Menu], name: &str, except: Uuid) -> bool {
+    menus.iter().any(|menu| {
+        !menu.retired && menu.id != except && menu.name.eq_ignore_ascii_case(name.trim())
+    })
+}
+
+#[async_trait]
+impl MenuRepository for InMemoryMenuRepository {
+    async fn list(&self) -> Result<Vec<Menu>, RepositoryError> {
+        Ok(self.menus.read().await.clone())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Menu>, RepositoryError> {
+        Ok(self
+            .menus
+            .read()
+            .await
+            .iter()
+            .find(|menu| menu.id == id)
+            .cloned())
+    }
+
+    async fn create(&self, menu: Menu) -> Result<(), RepositoryError> {
+        let mut menus = self.menus.write().await;
+        if menus.iter().any(|stored| stored.id == menu.id) {
+            return Err(duplicate_id(&menu));
+        }
+        if sku_taken(&menus, &menu.sku) {
+            return Err(duplicate_sku(&menu));
+        }
+        if name_taken(&menus, &menu.name, menu.id) {
+            return Err(duplicate_name(&menu.name));
+        }
+        let mut next = menus.clone();
+        next.push(menu);
+        self.persist(&next).await?;
+        *menus = next;
+        Ok(())
+    }
+
+    async fn update(&self, menu: Menu) -> Result<bool, RepositoryError> {
+        let mut menus = self.menus.write().await;
+        let Some(position) = menus
+            .iter()
+            .position(|stored| stored.id == menu.id && !stored.retired)
+        else {
+            return Ok(false);
+        };
+        if name_taken(&menus, &menu.name, menu.id) {
+            return Err(duplicate_name(&menu.name));
+        }
+        let mut next = menus.clone();
+        next[position] = menu;
+        self.persist(&next).await?;
+        *menus = next;
+        Ok(true)
+    }
+
+    async fn retire(&self, id: Uuid) -> Result<Option<Menu>, RepositoryError> {
+        let mut menus = self.menus.write().await;
+        let Some(position) = menus.iter().position(|stored| stored.id == id) else {
+            return Ok(None);
+        };
+        let mut next = menus.clone();
+        next[position].retired = true;
+        self.persist(&next).await?;
+        *menus = next;
+        Ok(Some(menus[position].clone()))
+    }
+
+    async fn apply_catalog(&self, catalog: Vec<Menu>) -> Result<(), RepositoryError> {
+        let mut menus = self.menus.write().await;
+        let mut next: Vec<Menu> = menus
+            .iter()
+            .map(|stored| Menu {
+                retired: true,
+                ..stored.clone()
+            })
+            .collect();
+        for menu in catalog {
+            match next.iter_mut().find(|stored| stored.id == menu.id) {
+                Some(stored) => *stored = menu,
+                None => next.push(menu),
+            }
+        }
+        self.persist(&next).await?;
+        *menus = next;
+        Ok(())
+    }
+
+    async fn stock_levels(&self) -> Result<HashMap<Uuid, u32>, RepositoryError> {
+        Ok(self.stock.lock().await.clone())
+    }
+
+    async fn set_stock(&self, id: Uuid, quantity: Option<u32>) -> Result<bool, RepositoryError> {
+        let menus = self.menus.read().await;
+        if !menus.iter().any(|menu| menu.id == id) {
+            return Ok(false);
+        }
+        let mut stock = self.stock.lock().await;
+        let mut next = stock.clone();
+        match quantity {
+            Some(quantity) => next.insert(id, quantity),
+            None => next.remove(&id),
+        };
+        self.persist_stock(&next).await?;
+        *stock = next;
+        Ok(true)
+    }
+
+    async fn reserve_stock(
+        &self,
+        wanted: &HashMap<Uuid, u32>,
+    ) -> Result<ReserveOutcome, RepositoryError> {
+        let mut stock = self.stock.lock().await;
+        let short = shortages(&stock, wanted);
+        if !short.is_empty() {
+            return Ok(ReserveOutcome::Short(short));
+        }
+        let mut next = stock.clone();
+        for (menu_id, requested) in wanted {
+            if let Some(left) = next.get_mut(menu_id) {
+                *left -= requested;
+            }
+        }
+        self.persist_stock(&next).await?;
+        *stock = next;
+        Ok(ReserveOutcome::Reserved)
+    }
+
+    async fn release_stock(&self, taken: &HashMap<Uuid, u32>) -> Result<(), RepositoryError> {
+        let mut stock = self.stock.lock().await;
+        let mut next = stock.clone();
+        for (menu_id, returned) in taken {
+            if let Some(left) = next.get_mut(menu_id) {
+                *left = left.saturating_add(*returned);
+            }
+        }
+        self.persist_stock(&next).await?;
+        *stock = next;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::model::{sku_from_name, CookingTimeProfile, Price};
+    use std::collections::HashSet;
+
+    fn menu(name: &str) -> Menu {
+        Menu::new_admin(
+            &sku_from_name(name, &HashSet::new()),
+            name,
+            Price::jpy(400),
+            CookingTimeProfile::uniform(3, 5),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_names_are_unique_among_active_menus() {
+        let repository = InMemoryMenuRepository::new(Vec::new());
+        let gyoza = menu("Gyoza");
+        repository.create(gyoza.clone()).await.unwrap();
+
+        let error = repository.create(menu("gyoza")).await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Duplicate(_)));
+
+        let retired = repository.retire(gyoza.id).await.unwrap().unwrap();
+        assert!(retired.retired);
+        repository.create(menu("Gyoza")).await.unwrap();
+        assert_eq!(repository.list().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_skips_retired_menus() {
+        let repository = InMemoryMenuRepository::new(Vec::new());
+        let mut edamame = menu("Edamame");
+        repository.create(edamame.clone()).await.unwrap();
+
+        edamame.name = "Salted edamame".to_string();
+        assert!(repository.update(edamame.clone()).await.unwrap());
+        assert_eq!(
+            repository.get(edamame.id).await.unwrap().unwrap().name,
+            "Salted edamame"
+        );
+
+        repository.retire(edamame.id).await.unwrap();
+        assert!(!repository.update(edamame).await.unwrap());
+        assert!(repository.retire(Uuid::new_v4()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_apply_catalog_retires_missing_menus() {
+        let gyoza = menu("Gyoza");
+        let edamame = menu("Edamame");
+        let repository = InMemoryMenuRepository::new(vec![gyoza.clone(), edamame.clone()]);
+        repository.retire(edamame.id).await.unwrap();
+
+        let renamed = Menu {
+            name: "Pan-fried gyoza".to_string(),
+            ..gyoza.clone()
+        };
+        let edamame_back = Menu {
+            retired: false,
+            ..edamame.clone()
+        };
+        let karaage = menu("Karaage");
+        repository
+            .apply_catalog(vec![edamame_back, karaage.clone()])
+            .await
+            .unwrap();
+        let stored = repository.list().await.unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored[0].retired);
+        assert!(!stored[1].retired);
+        assert_eq!(stored[2].id, karaage.id);
+
+        repository.apply_catalog(vec![renamed]).await.unwrap();
+        let stored = repository.get(gyoza.id).await.unwrap().unwrap();
+        assert_eq!(stored.name, "Pan-fried gyoza");
+        assert!(!stored.retired);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_stock_is_all_or_nothing() {
+        let beef = menu("Beef rice");
+        let beer = menu("Beer");
+        let edamame = menu("Edamame");
+        let repository =
+            InMemoryMenuRepository::new(vec![beef.clone(), beer.clone(), edamame.clone()]);
+        assert!(repository.set_stock(beef.id, Some(2)).await.unwrap());
+        assert!(repository.set_stock(beer.id, Some(10)).await.unwrap());
+        assert!(!repository.set_stock(Uuid::new_v4(), Some(1)).await.unwrap());
+
+        let wanted = HashMap::from([(beef.id, 3), (beer.id, 1), (edamame.id, 50)]);
+        match repository.reserve_stock(&wanted).await.unwrap() {
+            ReserveOutcome::Short(short) => {
+                assert_eq!(short.len(), 1);
+                assert_eq!(short[0].menu_id, beef.id);
+                assert_eq!(short[0].available, 2);
+            }
+            ReserveOutcome::Reserved => panic!("Beef rice should be short"),
+        }
+        assert_eq!(repository.stock_levels().await.unwrap()[&beer.id], 10);
+
+        let wanted = HashMap::from([(beef.id, 2), (beer.id, 1), (edamame.id, 50)]);
+        assert!(matches!(
+            repository.reserve_stock(&wanted).await.unwrap(),
+            ReserveOutcome::Reserved
+        ));
+        let levels = repository.stock_levels().await.unwrap();
+        assert_eq!(levels[&beef.id], 0);
+        assert_eq!(levels[&beer.id], 9);
+        assert!(!levels.contains_key(&edamame.id));
+
+        repository.release_stock(&wanted).await.unwrap();
+        assert_eq!(repository.stock_levels().await.unwrap()[&beef.id], 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_catalog_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("mealdiner-menus-{}.json", Uuid::new_v4()));
+        let karaage = menu("Karaage");
+        {
+            let repository = InMemoryMenuRepository::open_file(&path).await.unwrap();
+            assert!(repository.list().await.unwrap().is_empty());
+            repository.create(karaage.clone()).await.unwrap();
+            repository.set_stock(karaage.id, Some(4)).await.unwrap();
+            repository.retire(karaage.id).await.unwrap();
+        }
+
+        let repository = InMemoryMenuRepository::open_file(&path).await.unwrap();
+        let stored = repository.get(karaage.id).await.unwrap().unwrap();
+        assert_eq!(stored.name, "Karaage");
+        assert!(stored.retired);
+        assert_eq!(repository.stock_levels().await.unwrap()[&karaage.id], 4);
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(stock_path(&path));
+    }
+}
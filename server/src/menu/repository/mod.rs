@@ -0,0 +1,112 @@
+pub mod memory;
+pub mod sqlite;
+
+use crate::menu::model::Menu;
+use crate::storage::RepositoryError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub use memory::InMemoryMenuRepository;
+pub use sqlite::SqliteMenuRepository;
+
+/// A menu that cannot cover what was ordered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StockShortage {
+    pub menu_id: Uuid,
+    pub requested: u32,
+    pub available: u32,
+}
+
+#[derive(Debug)]
+pub enum ReserveOutcome {
+    Reserved,
+    Short(Vec<StockShortage>),
+}
+
+/// Storage boundary for the menu catalog. Names (ignoring case) and SKUs are
+/// unique among active menus; a retired menu frees both for reuse.
+#[async_trait]
+pub trait MenuRepository: Send + Sync {
+    /// Every stored menu, retired ones included, in the order they were added.
+    async fn list(&self) -> Result<Vec<Menu>, RepositoryError>;
+
+    async fn get(&self, id: Uuid) -> Result<Option<Menu>, RepositoryError>;
+
+    /// Fails with `RepositoryError::Duplicate` when the id is stored or an active
+    /// menu already has the name or SKU.
+    async fn create(&self, menu: Menu) -> Result<(), RepositoryError>;
+
+    /// Replace an active menu. Returns `false` when the id is unknown or retired.
+    async fn update(&self, menu: Menu) -> Result<bool, RepositoryError>;
+
+    /// Take a menu off sale. Returns the retired menu, or `None` for an unknown id.
+    async fn retire(&self, id: Uuid) -> Result<Option<Menu>, RepositoryError>;
+
+    /// Make `menus` the active catalog in one step: they are inserted or replaced
+    /// by id, and every other stored menu is retired. `menus` must already be
+    /// validated as a whole.
+    async fn apply_catalog(&self, menus: Vec<Menu>) -> Result<(), RepositoryError>;
+
+    /// Portions left of every menu whose stock is tracked. Untracked menus are
+    /// unlimited. Stock is kept apart from the menus so catalog reloads leave it alone.
+    async fn stock_levels(&self) -> Result<HashMap<Uuid, u32>, RepositoryError>;
+
+    /// Set a menu's stock, or stop tracking it with `None`. Returns `false` for an
+    /// unknown menu.
+    async fn set_stock(&self, id: Uuid, quantity: Option<u32>) -> Result<bool, RepositoryError>;
+
+    /// Take `wanted` portions per menu in one step. When any tracked menu is short,
+    /// nothing is taken and every shortage is returned.
+    async fn reserve_stock(
+        &self,
+        wanted: &HashMap<Uuid, u32>,
+    ) -> Result<ReserveOutcome, RepositoryError>;
+
+    /// Give back portions taken by `reserve_stock`, e.g. when the orders could not be stored.
+    async fn release_stock(&self, taken: &HashMap<Uuid, u32>) -> Result<(), RepositoryError>;
+}
+
+/// Fill an empty catalog with `menus`. A catalog that already has entries is left
+/// alone, so admin edits survive restarts.
+pub async fn seed_if_empty(
+    repository: &dyn MenuRepository,
+    menus: Vec<Menu>,
+) -> Result<(), RepositoryError> {
+    if !repository.list().await?.is_empty() {
+        return Ok(());
+    }
+    for menu in menus {
+        repository.create(menu).await?;
+    }
+    Ok(())
+}
+
+/// Shortages of `wanted` against `levels`; menus missing from `levels` are unlimited.
+fn shortages(levels: &HashMap<Uuid, u32>, wanted: &HashMap<Uuid, u32>) -> Vec<StockShortage> {
+    let mut short: Vec<StockShortage> = wanted
+        .iter()
+        .filter_map(|(&menu_id, &requested)| {
+            let available = *levels.get(&menu_id)?;
+            (available < requested).then_some(StockShortage {
+                menu_id,
+                requested,
+                available,
+            })
+        })
+        .collect();
+    short.sort_by_key(|shortage| shortage.menu_id);
+    short
+}
+
+fn duplicate_id(menu: &Menu) -> RepositoryError {
+    RepositoryError::Duplicate(format!("Menu {} already exists", menu.id))
+}
+
+fn duplicate_sku(menu: &Menu) -> RepositoryError {
+    RepositoryError::Duplicate(format!("Menu with SKU '{}' already exists", menu.sku))
+}
+
+fn duplicate_name(name: &str) -> RepositoryError {
+    RepositoryError::Duplicate(format!("Menu '{}' already exists", name.trim()))
+}
@@ -0,0 +1,377 @@
+use crate::menu::model::Menu;
+use crate::menu::repository::{
+    duplicate_id, duplicate_name, duplicate_sku, shortages, MenuRepository, ReserveOutcome,
+};
+use crate::storage::{RepositoryError, SqliteDatabase};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, ErrorCode};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Menus live in the same database file as orders. The partial unique index on
+/// `name` enforces the active-name rule even across concurrent writers.
+pub struct SqliteMenuRepository {
+    db: SqliteDatabase,
+}
+
+impl SqliteMenuRepository {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+}
+
+fn decode(payload: String) -> Result<Menu, RepositoryError> {
+    Ok(serde_json::from_str(&payload)?)
+}
+
+fn stock_levels(conn: &Connection) -> Result<HashMap<Uuid, u32>, RepositoryError> {
+    let mut stmt = conn.prepare_cached("SELECT menu_id, quantity FROM menu_stock")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    rows.into_iter()
+        .map(|(id, quantity)| {
+            let id = Uuid::parse_str(&id).map_err(|e| RepositoryError::Backend(e.to_string()))?;
+            Ok((id, quantity))
+        })
+        .collect()
+}
+
+fn fetch(conn: &Connection, id: Uuid) -> Result<Option<Menu>, RepositoryError> {
+    let mut stmt = conn.prepare_cached("SELECT payload FROM menus WHERE id = ?1")?;
+    let mut rows = stmt.query(params![id.to_string()])?;
+    match rows.next()? {
+        Some(row) => decode(row.get(0)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn sku_taken(conn: &Connection, sku: &str) -> Result<bool, RepositoryError> {
+    if sku.is_empty() {
+        return Ok(false);
+    }
+    let mut stmt = conn.prepare_cached(
+        "SELECT 1 FROM menus WHERE retired = 0 AND json_extract(payload, '$.sku') = ?1",
+    )?;
+    Ok(stmt.exists(params![sku])?)
+}
+
+/// Turn a violation of the active-name index into the error handlers report as a conflict.
+fn on_conflict(e: rusqlite::Error, menu: &Menu) -> RepositoryError {
+    match e.sqlite_error_code() {
+        Some(ErrorCode::ConstraintViolation) => duplicate_name(&menu.name),
+        _ => e.into(),
+    }
+}
+
+#[async_trait]
+impl MenuRepository for SqliteMenuRepository {
+    async fn list(&self) -> Result<Vec<Menu>, RepositoryError> {
+        self.db
+            .run(|conn| {
+                let mut stmt = conn.prepare_cached("SELECT payload FROM menus ORDER BY rowid")?;
+                let payloads = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                payloads.into_iter().map(decode).collect()
+            })
+            .await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Menu>, RepositoryError> {
+        self.db.run(move |conn| fetch(conn, id)).await
+    }
+
+    async fn create(&self, menu: Menu) -> Result<(), RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                if fetch(&tx, menu.id)?.is_some() {
+                    return Err(duplicate_id(&menu));
+                }
+                if sku_taken(&tx, &menu.sku)? {
+                    return Err(duplicate_sku(&menu));
+                }
+                tx.execute(
+                    "INSERT INTO menus (id, name, retired, payload) VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        menu.id.to_string(),
+                        menu.name.trim(),
+                        menu.retired,
+                        serde_json::to_string(&menu)?
+                    ],
+                )
+                .map_err(|e| on_conflict(e, &menu))?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update(&self, menu: Menu) -> Result<bool, RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let updated = conn
+                    .execute(
+                        "UPDATE menus SET name = ?1, payload = ?2 WHERE id = ?3 AND retired = 0",
+                        params![
+                            menu.name.trim(),
+                            serde_json::to_string(&menu)?,
+                            menu.id.to_string()
+                        ],
+                    )
+                    .map_err(|e| on_conflict(e, &menu))?;
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    async fn retire(&self, id: Uuid) -> Result<Option<Menu>, RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                let Some(mut menu) = fetch(&tx, id)? else {
+                    return Ok(None);
+                };
+                menu.retired = true;
+                tx.execute(
+                    "UPDATE menus SET retired = 1, payload = ?1 WHERE id = ?2",
+                    params![serde_json::to_string(&menu)?, id.to_string()],
+                )?;
+                tx.commit()?;
+                Ok(Some(menu))
+            })
+            .await
+    }
+
+    async fn apply_catalog(&self, catalog: Vec<Menu>) -> Result<(), RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                // Retire everything first so names can move between menus freely;
+                // the upserts below bring the catalog's menus back.
+                tx.execute(
+                    "UPDATE menus SET retired = 1, payload = json_set(payload, '$.retired', json('true'))
+                    WHERE retired = 0",
+                    [],
+                )?;
+                for menu in &catalog {
+                    tx.execute(
+                        "INSERT INTO menus (id, name, retired, payload) VALUES (?1, ?2, ?3, ?4)
+                        ON CONFLICT (id) DO UPDATE SET
+                            name = excluded.name,
+                            retired = excluded.retired,
+                            payload = excluded.payload",
+                        params![
+                            menu.id.to_string(),
+                            menu.name.trim(),
+                            menu.retired,
+                            serde_json::to_string(menu)?
+                        ],
+                    )
+                    .map_err(|e| on_conflict(e, menu))?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn stock_levels(&self) -> Result<HashMap<Uuid, u32>, RepositoryError> {
+        self.db.run(|conn| stock_levels(conn)).await
+    }
+
+    async fn set_stock(&self, id: Uuid, quantity: Option<u32>) -> Result<bool, RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                if fetch(&tx, id)?.is_none() {
+                    return Ok(false);
+                }
+                match quantity {
+                    Some(quantity) => tx.execute(
+                        "INSERT INTO menu_stock (menu_id, quantity) VALUES (?1, ?2)
+                        ON CONFLICT (menu_id) DO UPDATE SET quantity = excluded.quantity",
+                        params![id.to_string(), quantity],
+                    )?,
+                    None => tx.execute(
+                        "DELETE FROM menu_stock WHERE menu_id = ?1",
+                        params![id.to_string()],
+                    )?,
+                };
+                tx.commit()?;
+                Ok(true)
+            })
+            .await
+    }
+
+    async fn reserve_stock(
+        &self,
+        wanted: &HashMap<Uuid, u32>,
+    ) -> Result<ReserveOutcome, RepositoryError> {
+        let wanted = wanted.clone();
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                let short = shortages(&stock_levels(&tx)?, &wanted);
+                if !short.is_empty() {
+                    return Ok(ReserveOutcome::Short(short));
+                }
+                for (menu_id, requested) in &wanted {
+                    tx.execute(
+                        "UPDATE menu_stock SET quantity = quantity - ?1 WHERE menu_id = ?2",
+                        params![requested, menu_id.to_string()],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(ReserveOutcome::Reserved)
+            })
+            .await
+    }
+
+    async fn release_stock(&self, taken: &HashMap<Uuid, u32>) -> Result<(), RepositoryError> {
+        let taken = taken.clone();
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                for (menu_id, returned) in &taken {
+                    tx.execute(
+                        "UPDATE menu_stock SET quantity = quantity + ?1 WHERE menu_id = ?2",
+                        params![returned, menu_id.to_string()],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::model::{sku_from_name, CookingTimeProfile, Price};
+    use std::collections::HashSet;
+
+    fn menu(name: &str) -> Menu {
+        Menu::new_admin(
+            &sku_from_name(name, &HashSet::new()),
+            name,
+            Price::jpy(400),
+            CookingTimeProfile::uniform(3, 5),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_create_update_retire() {
+        let repository = SqliteMenuRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let mut gyoza = menu("Gyoza");
+        repository.create(gyoza.clone()).await.unwrap();
+        repository.create(menu("Edamame")).await.unwrap();
+
+        let error = repository.create(menu("GYOZA")).await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Duplicate(_)));
+        let same_sku = Menu {
+            id: Uuid::new_v4(),
+            name: "Gyoza again".to_string(),
+            ..gyoza.clone()
+        };
+        let error = repository.create(same_sku).await.unwrap_err();
+        assert!(error.to_string().contains("SKU"));
+        let same_id = Menu {
+            sku: "gyoza-again".to_string(),
+            name: "Gyoza again".to_string(),
+            ..gyoza.clone()
+        };
+        let error = repository.create(same_id).await.unwrap_err();
+        assert!(error.to_string().contains(&gyoza.id.to_string()));
+
+        gyoza.name = "Edamame".to_string();
+        let error = repository.update(gyoza.clone()).await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Duplicate(_)));
+
+        gyoza.name = "Pan-fried gyoza".to_string();
+        assert!(repository.update(gyoza.clone()).await.unwrap());
+
+        let retired = repository.retire(gyoza.id).await.unwrap().unwrap();
+        assert!(retired.retired);
+        assert_eq!(retired.name, "Pan-fried gyoza");
+        assert!(!repository.update(gyoza.clone()).await.unwrap());
+        repository.create(menu("Pan-fried gyoza")).await.unwrap();
+
+        let names: Vec<String> = repository
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|menu| menu.name)
+            .collect();
+        assert_eq!(names, ["Pan-fried gyoza", "Edamame", "Pan-fried gyoza"]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_catalog() {
+        let repository = SqliteMenuRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let gyoza = menu("Gyoza");
+        repository.create(gyoza.clone()).await.unwrap();
+
+        // The new catalog reuses the name of the menu it drops.
+        let replacement = menu("Gyoza");
+        repository
+            .apply_catalog(vec![replacement.clone()])
+            .await
+            .unwrap();
+
+        assert!(repository.get(gyoza.id).await.unwrap().unwrap().retired);
+        assert!(
+            !repository
+                .get(replacement.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .retired
+        );
+
+        let renamed = Menu {
+            name: "Gyoza (6 pcs)".to_string(),
+            ..replacement.clone()
+        };
+        repository.apply_catalog(vec![renamed]).await.unwrap();
+        let stored = repository.list().await.unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].name, "Gyoza (6 pcs)");
+    }
+
+    #[tokio::test]
+    async fn test_stock_reservation() {
+        let repository = SqliteMenuRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let beef = menu("Beef rice");
+        let beer = menu("Beer");
+        repository.create(beef.clone()).await.unwrap();
+        repository.create(beer.clone()).await.unwrap();
+        assert!(repository.set_stock(beef.id, Some(1)).await.unwrap());
+        assert!(!repository.set_stock(Uuid::new_v4(), Some(1)).await.unwrap());
+
+        let wanted = HashMap::from([(beef.id, 2), (beer.id, 5)]);
+        assert!(matches!(
+            repository.reserve_stock(&wanted).await.unwrap(),
+            ReserveOutcome::Short(_)
+        ));
+        assert_eq!(repository.stock_levels().await.unwrap()[&beef.id], 1);
+
+        let wanted = HashMap::from([(beef.id, 1), (beer.id, 5)]);
+        assert!(matches!(
+            repository.reserve_stock(&wanted).await.unwrap(),
+            ReserveOutcome::Reserved
+        ));
+        assert_eq!(repository.stock_levels().await.unwrap()[&beef.id], 0);
+
+        repository.release_stock(&wanted).await.unwrap();
+        assert_eq!(repository.stock_levels().await.unwrap()[&beef.id], 1);
+
+        assert!(repository.set_stock(beef.id, None).await.unwrap());
+        assert!(repository.stock_levels().await.unwrap().is_empty());
+    }
+}
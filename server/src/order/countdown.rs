@@ -0,0 +1,168 @@
+use crate::app_state::AppState;
+use crate::order::model::OrderStatus;
+use crate::order::repository::{OrderRepository, RepositoryError, UpdateOutcome};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+pub const READY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Flag every unfinished order whose countdown has elapsed as ready.
+/// Returns how many orders changed.
+pub async fn flag_ready_orders(
+    orders: &dyn OrderRepository,
+    now: DateTime<Utc>,
+) -> Result<usize, RepositoryError> {
+    let due: Vec<_> = orders
+        .list_by_status(&[OrderStatus::Pending, OrderStatus::Cooking])
+        .await?
+        .into_iter()
+        .filter(|order| order.ready_at <= now)
+        .collect();
+
+    let mut flagged = 0;
+    for order in due {
+        // Re-checked inside the update, staff may have served or cancelled it meanwhile.
+        let outcome = orders
+            .update(
+                order.table_id,
+                order.id,
+                Box::new(move |order| order.mark_ready_if_due(now)),
+            )
+            .await?;
+        if let UpdateOutcome::Updated(_) = outcome {
+            flagged += 1;
+        }
+    }
+    Ok(flagged)
+}
+
+/// Periodically let the estimator move `ready_at` as the backlog drains, then flag
+/// whatever is due as ready.
+pub fn spawn_ready_watcher(state: Arc<AppState>, every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        loop {
+            ticker.tick().await;
+            let now = Utc::now();
+            match state.estimator.reschedule(now).await {
+                Ok(0) => {}
+                Ok(moved) => debug!("Rescheduled {} orders", moved),
+                Err(e) => error!("Failed to reschedule orders: {}", e),
+            }
+            match flag_ready_orders(state.orders.as_ref(), now).await {
+                Ok(0) => {}
+                Ok(flagged) => debug!("Flagged {} orders as ready", flagged),
+                Err(e) => error!("Failed to flag ready orders: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::model::CookingTimeProfile;
+    use crate::order::estimator::{
+        CookingTimeEstimator, KitchenItem, KitchenLoadEstimator, RandomCookingTime,
+    };
+    use crate::order::model::Order;
+    use crate::order::repository::InMemoryOrderRepository;
+    use chrono::Duration as ChronoDuration;
+
+    #[tokio::test]
+    async fn test_flag_ready_orders_only_when_due() {
+        let repository = InMemoryOrderRepository::new();
+        let quick = Order::for_test(1).cooked_from(Utc::now(), 5);
+        let slow = Order::for_test(1).cooked_from(Utc::now(), 15);
+        repository
+            .create(vec![quick.clone(), slow.clone()])
+            .await
+            .unwrap();
+
+        let now = Utc::now() + ChronoDuration::minutes(10);
+        assert_eq!(flag_ready_orders(&repository, now).await.unwrap(), 1);
+
+        let quick = repository.get(1, quick.id).await.unwrap().unwrap();
+        let slow = repository.get(1, slow.id).await.unwrap().unwrap();
+        assert_eq!(quick.status, OrderStatus::Ready);
+        assert_eq!(slow.status, OrderStatus::Pending);
+        assert_eq!(quick.remaining_seconds(now), 0);
+        assert!(slow.remaining_seconds(now) > 0);
+
+        // Already ready orders are left alone on the next pass.
+        assert_eq!(flag_ready_orders(&repository, now).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_orders_are_not_flagged() {
+        let repository = InMemoryOrderRepository::new();
+        let order = Order::for_test(1).cooked_from(Utc::now(), 5);
+        repository.create(vec![order.clone()]).await.unwrap();
+        repository
+            .update(
+                1,
+                order.id,
+                Box::new(|order| order.transition_to(OrderStatus::Cancelled)),
+            )
+            .await
+            .unwrap();
+
+        let now = Utc::now() + ChronoDuration::minutes(30);
+        assert_eq!(flag_ready_orders(&repository, now).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_watcher_follows_the_state_machine_with_either_estimator() {
+        let start = Utc::now();
+        let repository = Arc::new(InMemoryOrderRepository::new());
+        let estimators: [Arc<dyn CookingTimeEstimator>; 2] = [
+            Arc::new(RandomCookingTime),
+            Arc::new(KitchenLoadEstimator::new(repository.clone(), 1)),
+        ];
+        let item = KitchenItem {
+            profile: CookingTimeProfile::uniform(2, 2),
+            quantity: 1,
+        };
+        for estimator in estimators {
+            let mut placed = Vec::new();
+            for _ in 0..2 {
+                let estimate = estimator.estimate(&[item], start).await.unwrap()[0];
+                let order = Order {
+                    ready_at: estimate.ready_at,
+                    ..Order::for_test(1).cooked_from(start, estimate.cooking_time_minutes)
+                };
+                repository.create(vec![order.clone()]).await.unwrap();
+                placed.push(order);
+            }
+            // Staff picked up the first one, the second is still waiting.
+            repository
+                .update(
+                    1,
+                    placed[0].id,
+                    Box::new(|order| order.transition_to(OrderStatus::Cooking)),
+                )
+                .await
+                .unwrap();
+
+            let mut now = start;
+            while now < start + ChronoDuration::minutes(5) {
+                now += ChronoDuration::seconds(5);
+                estimator.reschedule(now).await.unwrap();
+                flag_ready_orders(repository.as_ref(), now).await.unwrap();
+            }
+            for order in &placed {
+                let mut order = repository.get(1, order.id).await.unwrap().unwrap();
+                assert_eq!(order.status, OrderStatus::Ready);
+                // Flagged orders carry on through the state machine like any other.
+                order.transition_to(OrderStatus::Served).unwrap();
+            }
+            repository
+                .clear_table(1, &OrderStatus::ALL, false)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use crate::app_state::OrderStorage;
+use crate::config::model::EstimatorKind;
+use crate::menu::model::{CookingTimeDistribution, CookingTimeProfile};
+use crate::order::model::{Order, OrderStatus, OrderUpdateError};
+use crate::order::repository::{RepositoryError, UpdateOutcome};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+/// When a new item should be ready, and how long it takes once a cook picks it up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    pub cooking_time_minutes: u32,
+    pub ready_at: DateTime<Utc>,
+}
+
+/// A new order line to estimate: `quantity` portions of one dish.
+#[derive(Debug, Clone, Copy)]
+pub struct KitchenItem {
+    pub profile: CookingTimeProfile,
+    pub quantity: u32,
+}
+
+/// Strategy for how long items will take. Swappable on `AppState`.
+#[async_trait]
+pub trait CookingTimeEstimator: Send + Sync {
+    /// Estimate a batch of new items ordered at `now`, one estimate per item.
+    /// `cooking_time_minutes` is per portion.
+    async fn estimate(
+        &self,
+        items: &[KitchenItem],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Estimate>, RepositoryError>;
+
+    /// Revisit `ready_at` of orders already waiting. Returns how many were moved.
+    /// Estimators that don't depend on other orders have nothing to do.
+    async fn reschedule(&self, _now: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        Ok(0)
+    }
+}
+
+pub fn build_estimator(
+    kind: &EstimatorKind,
+    orders: OrderStorage,
+) -> Arc<dyn CookingTimeEstimator> {
+    match kind {
+        EstimatorKind::Random => Arc::new(RandomCookingTime),
+        EstimatorKind::KitchenLoad { stations } => {
+            Arc::new(KitchenLoadEstimator::new(orders, *stations))
+        }
+    }
+}
+
+/// Draw a cooking time from the menu's own profile.
+pub fn sample_minutes(profile: &CookingTimeProfile) -> u32 {
+    let (min, max) = (
+        profile.min_minutes,
+        profile.max_minutes.max(profile.min_minutes),
+    );
+    if min == max {
+        return min;
+    }
+    let mut rng = rand::rng();
+    match profile.distribution {
+        CookingTimeDistribution::Uniform => rng.random_range(min..=max),
+        CookingTimeDistribution::Triangular { mode_minutes } => {
+            let (a, b) = (f64::from(min), f64::from(max));
+            let c = f64::from(mode_minutes.clamp(min, max));
+            // Inverse CDF of the triangular distribution.
+            let u: f64 = rng.random();
+            let sample = if u < (c - a) / (b - a) {
+                a + (u * (b - a) * (c - a)).sqrt()
+            } else {
+                b - ((1.0 - u) * (b - a) * (b - c)).sqrt()
+            };
+            (sample.round() as u32).clamp(min, max)
+        }
+    }
+}
+
+/// Every item starts cooking the moment it is ordered, all portions of it at once.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomCookingTime;
+
+#[async_trait]
+impl CookingTimeEstimator for RandomCookingTime {
+    async fn estimate(
+        &self,
+        items: &[KitchenItem],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Estimate>, RepositoryError> {
+        Ok(items
+            .iter()
+            .map(|item| {
+                let cooking_time_minutes = sample_minutes(&item.profile);
+                Estimate {
+                    cooking_time_minutes,
+                    ready_at: Order::ready_at_for(now, cooking_time_minutes),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Queue-based estimates: `stations` cooks work through the unfinished orders first come,
+/// first served, and a new item is ready once a station frees up and has cooked it. A station
+/// cooks the portions of one line one after another, so a line of 3 takes it 3 times as long.
+pub struct KitchenLoadEstimator {
+    orders: OrderStorage,
+    stations: usize,
+}
+
+impl KitchenLoadEstimator {
+    pub fn new(orders: OrderStorage, stations: usize) -> Self {
+        Self {
+            orders,
+            stations: stations.max(1),
+        }
+    }
+
+    async fn backlog(&self) -> Result<Vec<Order>, RepositoryError> {
+        self.orders
+            .list_by_status(&[OrderStatus::Pending, OrderStatus::Cooking])
+            .await
+    }
+}
+
+#[async_trait]
+impl CookingTimeEstimator for KitchenLoadEstimator {
+    async fn estimate(
+        &self,
+        items: &[KitchenItem],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Estimate>, RepositoryError> {
+        // Concurrent requests may plan against the same backlog; the next
+        // `reschedule` pass straightens that out.
+        let (mut kitchen, _) = Kitchen::load(self.backlog().await?, self.stations, now);
+        Ok(items
+            .iter()
+            .map(|item| {
+                let cooking_time_minutes = sample_minutes(&item.profile);
+                Estimate {
+                    cooking_time_minutes,
+                    ready_at: kitchen
+                        .assign(now, station_time(cooking_time_minutes, item.quantity)),
+                }
+            })
+            .collect())
+    }
+
+    async fn reschedule(&self, now: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let (_, planned) = Kitchen::load(self.backlog().await?, self.stations, now);
+        let mut moved = 0;
+        for (order, ready_at) in planned {
+            if (order.ready_at - ready_at).num_seconds().abs() < 1 {
+                continue;
+            }
+            let outcome = self
+                .orders
+                .update(
+                    order.table_id,
+                    order.id,
+                    Box::new(move |order| {
+                        if !order.status.is_unfinished() {
+                            return Err(OrderUpdateError::Finished(order.status));
+                        }
+                        order.ready_at = ready_at;
+                        Ok(())
+                    }),
+                )
+                .await?;
+            if let UpdateOutcome::Updated(_) = outcome {
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+}
+
+/// How long a line keeps its station busy.
+fn station_time(cooking_time_minutes: u32, quantity: u32) -> Duration {
+    Duration::minutes(i64::from(cooking_time_minutes) * i64::from(quantity.max(1)))
+}
+
+/// When each station becomes free, earliest first.
+struct Kitchen {
+    free_at: BinaryHeap<Reverse<DateTime<Utc>>>,
+}
+
+impl Kitchen {
+    /// Replay the backlog onto `stations` idle stations: orders already cooking keep their
+    /// station, pending ones queue up in the order they were placed. A pending order whose
+    /// planned start has passed stays anchored there, so replaying it every pass does not
+    /// keep pushing its `ready_at` out; only a station freeing up earlier moves it, forward.
+    /// Returns the loaded kitchen and the planned `ready_at` of every backlog order.
+    fn load(
+        mut backlog: Vec<Order>,
+        stations: usize,
+        now: DateTime<Utc>,
+    ) -> (Self, Vec<(Order, DateTime<Utc>)>) {
+        let mut kitchen = Kitchen {
+            free_at: (0..stations)
+                .map(|_| Reverse(DateTime::<Utc>::MIN_UTC))
+                .collect(),
+        };
+        backlog.sort_by_key(|order| (order.status != OrderStatus::Cooking, order.created_at));
+
+        let mut planned = Vec::with_capacity(backlog.len());
+        for order in backlog {
+            let cooking = station_time(order.cooking_time_minutes, order.quantity);
+            let ready_at = if order.status == OrderStatus::Cooking {
+                kitchen.occupy_until(order.ready_at.clamp(now, now + cooking))
+            } else {
+                let planned_start = order.ready_at - cooking;
+                kitchen.assign(planned_start.min(now), cooking)
+            };
+            planned.push((order, ready_at));
+        }
+        (kitchen, planned)
+    }
+
+    /// Put an item on the first station to free up, starting no earlier than `earliest`.
+    /// Returns when it will be done.
+    fn assign(&mut self, earliest: DateTime<Utc>, cooking: Duration) -> DateTime<Utc> {
+        let start = self
+            .free_at
+            .pop()
+            .map_or(earliest, |Reverse(at)| at.max(earliest));
+        let done = start + cooking;
+        self.free_at.push(Reverse(done));
+        done
+    }
+
+    /// Keep the first free station busy until `done`.
+    fn occupy_until(&mut self, done: DateTime<Utc>) -> DateTime<Utc> {
+        let start = self.free_at.pop().map_or(done, |Reverse(at)| at);
+        let done = done.max(start);
+        self.free_at.push(Reverse(done));
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::countdown::flag_ready_orders;
+    use crate::order::repository::{InMemoryOrderRepository, OrderRepository};
+
+    fn single(profile: CookingTimeProfile) -> KitchenItem {
+        KitchenItem {
+            profile,
+            quantity: 1,
+        }
+    }
+
+    #[test]
+    fn test_random_cooking_time() {
+        let profile = CookingTimeProfile::default();
+        for _ in 0..100 {
+            let cooking_time = sample_minutes(&profile);
+            assert!((5..=15).contains(&cooking_time));
+        }
+    }
+
+    #[test]
+    fn test_random_cooking_time_respects_profile() {
+        let beer = CookingTimeProfile::uniform(1, 2);
+        let ramen = CookingTimeProfile::triangular(8, 10, 15);
+        for _ in 0..100 {
+            assert!((1..=2).contains(&sample_minutes(&beer)));
+            assert!((8..=15).contains(&sample_minutes(&ramen)));
+        }
+    }
+
+    #[test]
+    fn test_triangular_centers_on_mode() {
+        let profile = CookingTimeProfile::triangular(0, 10, 20);
+        let samples: Vec<u32> = (0..2_000).map(|_| sample_minutes(&profile)).collect();
+        let mean = samples.iter().sum::<u32>() as f64 / samples.len() as f64;
+        assert!((9.0..=11.0).contains(&mean), "mean was {}", mean);
+    }
+
+    #[tokio::test]
+    async fn test_random_estimate_starts_immediately() {
+        let now = Utc::now();
+        let estimates = RandomCookingTime
+            .estimate(&[single(CookingTimeProfile::uniform(3, 3))], now)
+            .await
+            .unwrap();
+        assert_eq!(estimates[0].cooking_time_minutes, 3);
+        assert_eq!(estimates[0].ready_at, now + Duration::minutes(3));
+    }
+
+    #[tokio::test]
+    async fn test_kitchen_load_queues_behind_backlog() {
+        let now = Utc::now();
+        let repository = Arc::new(InMemoryOrderRepository::new());
+        // Two stations, both busy with 10 minute dishes.
+        repository
+            .create(vec![
+                Order::for_test(1).cooked_from(now, 10),
+                Order::for_test(1).cooked_from(now, 10),
+            ])
+            .await
+            .unwrap();
+        let estimator = KitchenLoadEstimator::new(repository.clone(), 2);
+
+        let fixed = CookingTimeProfile::uniform(4, 4);
+        let estimates = estimator
+            .estimate(&[single(fixed), single(fixed), single(fixed)], now)
+            .await
+            .unwrap();
+        let ready: Vec<i64> = estimates
+            .iter()
+            .map(|estimate| (estimate.ready_at - now).num_minutes())
+            .collect();
+        assert_eq!(ready, vec![14, 14, 18]);
+        assert!(estimates.iter().all(|e| e.cooking_time_minutes == 4));
+
+        // An idle kitchen starts right away.
+        let idle = KitchenLoadEstimator::new(Arc::new(InMemoryOrderRepository::new()), 2);
+        let estimate = idle.estimate(&[single(fixed)], now).await.unwrap()[0];
+        assert_eq!(estimate.ready_at, now + Duration::minutes(4));
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_pulls_orders_forward_as_backlog_drains() {
+        let now = Utc::now();
+        let repository = Arc::new(InMemoryOrderRepository::new());
+        let first = Order::for_test(1).cooked_from(now, 10);
+        let mut second = Order::for_test(1).cooked_from(now + Duration::seconds(1), 5);
+        // Planned while `first` occupied the only station.
+        second.ready_at = now + Duration::minutes(15);
+        repository
+            .create(vec![first.clone(), second.clone()])
+            .await
+            .unwrap();
+        let estimator = KitchenLoadEstimator::new(repository.clone(), 1);
+
+        assert_eq!(estimator.reschedule(now).await.unwrap(), 0);
+
+        // The first dish was cancelled, so the station is free for the second one now.
+        repository
+            .update(
+                1,
+                first.id,
+                Box::new(|order| order.transition_to(OrderStatus::Cancelled)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(estimator.reschedule(now).await.unwrap(), 1);
+        let second = repository.get(1, second.id).await.unwrap().unwrap();
+        assert_eq!(second.ready_at, now + Duration::minutes(5));
+    }
+
+    #[tokio::test]
+    async fn test_cooking_orders_keep_their_station() {
+        let now = Utc::now();
+        let repository = Arc::new(InMemoryOrderRepository::new());
+        let early = Order::for_test(1).cooked_from(now - Duration::minutes(8), 10);
+        let cooking = Order::for_test(1).cooked_from(now - Duration::minutes(1), 6);
+        repository
+            .create(vec![early.clone(), cooking.clone()])
+            .await
+            .unwrap();
+        repository
+            .update(
+                1,
+                cooking.id,
+                Box::new(|order| order.transition_to(OrderStatus::Cooking)),
+            )
+            .await
+            .unwrap();
+        let estimator = KitchenLoadEstimator::new(repository.clone(), 1);
+
+        estimator.reschedule(now).await.unwrap();
+        let cooking = repository.get(1, cooking.id).await.unwrap().unwrap();
+        let early = repository.get(1, early.id).await.unwrap().unwrap();
+        // The dish on the stove finishes first, the pending one waits for the station.
+        assert_eq!(cooking.ready_at, now + Duration::minutes(5));
+        assert_eq!(early.ready_at, now + Duration::minutes(15));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_reschedule_lets_pending_orders_come_due() {
+        let start = Utc::now();
+        let repository = Arc::new(InMemoryOrderRepository::new());
+        let order = Order::for_test(1).cooked_from(start, 2);
+        // Planned as if the station were free; the first pass queues it behind `order`.
+        let queued = Order::for_test(1).cooked_from(start + Duration::seconds(1), 3);
+        let estimator = KitchenLoadEstimator::new(repository.clone(), 1);
+        repository
+            .create(vec![order.clone(), queued.clone()])
+            .await
+            .unwrap();
+
+        // The ready watcher's passes, every 5 seconds, while nobody touches the orders.
+        let mut now = start;
+        while now < start + Duration::minutes(6) {
+            now += Duration::seconds(5);
+            estimator.reschedule(now).await.unwrap();
+            flag_ready_orders(repository.as_ref(), now).await.unwrap();
+        }
+        let order = repository.get(1, order.id).await.unwrap().unwrap();
+        let queued = repository.get(1, queued.id).await.unwrap().unwrap();
+        assert_eq!(order.ready_at, start + Duration::minutes(2));
+        assert_eq!(queued.ready_at, start + Duration::minutes(5));
+        assert_eq!(order.remaining_seconds(now), 0);
+        assert_eq!(queued.status, OrderStatus::Ready);
+    }
+
+    #[tokio::test]
+    async fn test_station_time_scales_with_quantity() {
+        let now = Utc::now();
+        let repository = Arc::new(InMemoryOrderRepository::new());
+        let estimator = KitchenLoadEstimator::new(repository.clone(), 1);
+        let line = KitchenItem {
+            profile: CookingTimeProfile::uniform(4, 4),
+            quantity: 3,
+        };
+        // Three 4 minute portions hold the station for 12 minutes.
+        let estimate = estimator.estimate(&[line], now).await.unwrap()[0];
+        assert_eq!(estimate.cooking_time_minutes, 4);
+        assert_eq!(estimate.ready_at, now + Duration::minutes(12));
+
+        repository
+            .create(vec![Order {
+                quantity: 3,
+                ready_at: estimate.ready_at,
+                ..Order::for_test(1).cooked_from(now, 4)
+            }])
+            .await
+            .unwrap();
+        let fixed = CookingTimeProfile::uniform(2, 2);
+        let estimate = estimator.estimate(&[single(fixed)], now).await.unwrap()[0];
+        assert_eq!(estimate.ready_at, now + Duration::minutes(14));
+        // The stored line replays onto the same 12 minutes.
+        assert_eq!(estimator.reschedule(now).await.unwrap(), 0);
+    }
+}
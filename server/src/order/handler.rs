@@ -1,89 +1,498 @@
 use crate::app_state::AppState;
-use crate::config::handler::get_config_internally;
-use crate::order::model::{CreateOrderRequest, Order};
-use axum::extract::State;
+use crate::config::handler::store_time;
+use crate::error::ApiError;
+use crate::handler::internal_error;
+use crate::locale::PreferredLocales;
+use crate::menu::combo::expand_combo;
+use crate::menu::model::{Menu, Price};
+use crate::menu::modifier::{select_modifiers, ModifierChoice, SelectedModifier};
+use crate::menu::repository::{ReserveOutcome, StockShortage};
+use crate::order::estimator::KitchenItem;
+use crate::order::model::{
+    ComboLine, CreateOrderRequest, InvalidLine, MenuData, Order, OrderLineRequest, OrderResponse,
+    OrderStatus,
+};
+use crate::order::ticket::{TicketRef, TicketResponse};
+use crate::session::model::TableSession;
+use crate::table::handler::{ensure_table_exists, give_back_stock};
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
-use chrono::Utc;
-use rand::Rng;
+use chrono::{DateTime, NaiveTime, Utc};
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 pub async fn create_orders(
     State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
     Json(payload): Json<CreateOrderRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let config = get_config_internally();
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(payload.table_id)?;
+    let session = state
+        .sessions
+        .active(payload.table_id)
+        .await?
+        .ok_or_else(|| no_open_session(payload.table_id))?;
+
+    let now = Utc::now();
+    let catalog = state.menus.list().await?;
+    let lines =
+        resolve_lines(&catalog, &payload.menus, store_time(now)).map_err(ApiError::InvalidLines)?;
 
-    if !(config.table_range.0..=config.table_range.1).contains(&payload.table_id) {
-        return Err(StatusCode::BAD_REQUEST);
+    let wanted = portions_by_menu(&lines);
+    if let ReserveOutcome::Short(short) = state.menus.reserve_stock(&wanted).await? {
+        return Err(ApiError::InvalidLines(shortage_lines(&lines, &short)));
     }
+    let ticket = state.tickets.next();
+    let new_orders = match place_orders(&state, &session, ticket, &lines, now).await {
+        Ok(orders) => orders,
+        Err(e) => {
+            // Nothing was ordered, so the portions go back on the shelf.
+            if let Err(release_error) = state.menus.release_stock(&wanted).await {
+                error!("Failed to give back reserved stock: {}", release_error);
+            }
+            return Err(e);
+        }
+    };
 
-    let mut new_orders = Vec::new();
-    let now = Utc::now();
+    info!(
+        "Created ticket #{} for table: {:?}",
+        ticket.number, payload.table_id
+    );
+
+    let data: Vec<OrderResponse> = new_orders
+        .into_iter()
+        .map(|order| OrderResponse::at(order, now).localized(&locales))
+        .collect();
+    let response = Json(json!({
+        "status": "success",
+        "data": data
+    }));
+
+    Ok(response)
+}
+
+/// A request line matched against the catalog, with its modifiers checked.
+/// A combo line also holds the portions the kitchen cooks for it.
+struct ResolvedLine<'a> {
+    menu: &'a Menu,
+    quantity: u32,
+    note: Option<String>,
+    modifiers: Vec<SelectedModifier>,
+    portions: Vec<Portion<'a>>,
+}
+
+/// One dish of a set meal with the options picked for it.
+struct Portion<'a> {
+    menu: &'a Menu,
+    modifiers: Vec<SelectedModifier>,
+}
+
+impl<'a> ResolvedLine<'a> {
+    /// What the kitchen cooks for this line: the menu itself, or a set's portions.
+    fn kitchen_dishes(&self) -> Vec<(&'a Menu, &[SelectedModifier])> {
+        if self.portions.is_empty() {
+            vec![(self.menu, &self.modifiers)]
+        } else {
+            self.portions
+                .iter()
+                .map(|portion| (portion.menu, portion.modifiers.as_slice()))
+                .collect()
+        }
+    }
+}
+
+/// Estimate and store one order per dish the kitchen cooks, all on `ticket` of
+/// `session`. A set meal becomes one order per portion, tied together by a
+/// shared combo line.
+async fn place_orders(
+    state: &AppState,
+    session: &TableSession,
+    ticket: TicketRef,
+    lines: &[ResolvedLine<'_>],
+    now: DateTime<Utc>,
+) -> Result<Vec<Order>, ApiError> {
+    let table_id = session.table_id;
+    type Dish<'l> = (
+        &'l ResolvedLine<'l>,
+        Option<Box<ComboLine>>,
+        &'l Menu,
+        &'l [SelectedModifier],
+    );
+    let dishes: Vec<Dish<'_>> = lines
+        .iter()
+        .flat_map(|line| {
+            let combo = line.menu.is_combo().then(|| {
+                Box::new(ComboLine {
+                    line_id: Uuid::new_v4(),
+                    menu: MenuData::from_menu(line.menu),
+                    unit_price: line.menu.price.clone(),
+                })
+            });
+            line.kitchen_dishes()
+                .into_iter()
+                .map(move |(menu, modifiers)| (line, combo.clone(), menu, modifiers))
+        })
+        .collect();
+    let items: Vec<KitchenItem> = dishes
+        .iter()
+        .map(|(line, _, menu, _)| KitchenItem {
+            profile: menu.cooking_time,
+            quantity: line.quantity,
+        })
+        .collect();
+    let estimates = state.estimator.estimate(&items, now).await?;
 
     // Iterate through each input menus and treat them individually into order
-    for menu in payload.menus {
-        let order = Order {
+    let new_orders: Vec<Order> = dishes
+        .into_iter()
+        .zip(estimates)
+        .map(|((line, combo, menu, modifiers), estimate)| Order {
             id: Uuid::new_v4(),
-            table_id: payload.table_id,
-            menu,
-            cooking_time_minutes: random_cooking_time(),
+            table_id,
+            ticket: Some(ticket),
+            session_id: Some(session.id),
+            menu: MenuData::from_menu(menu),
+            unit_price: match combo {
+                Some(_) => Price::new(0, &menu.price.currency).with_modifiers(modifiers),
+                None => menu.price.with_modifiers(modifiers),
+            },
+            quantity: line.quantity,
+            note: line.note.clone(),
+            modifiers: modifiers.to_vec(),
+            allergen_alerts: menu.allergens_among(&session.allergens),
+            combo,
+            amendments: Vec::new(),
+            cooking_time_minutes: estimate.cooking_time_minutes,
             created_at: now,
-        };
-        new_orders.push(order);
+            ready_at: estimate.ready_at,
+            status: OrderStatus::Pending,
+        })
+        .collect();
+
+    {
+        // The session may have closed while the order was being planned.
+        let _seating = state.seating.ordering(table_id).await;
+        let still_open = state.sessions.active(table_id).await?;
+        if still_open.map(|open| open.id) != Some(session.id) {
+            return Err(no_open_session(table_id));
+        }
+        state.orders.create(new_orders.clone()).await?;
+    }
+    for order in new_orders.iter().filter(|o| !o.allergen_alerts.is_empty()) {
+        warn!(
+            "Table {} ordered {} containing declared allergens {:?}",
+            table_id, order.menu.name, order.allergen_alerts
+        );
     }
+    Ok(new_orders)
+}
 
-    let mut orders = state.orders.write().await;
-    orders
-        .entry(payload.table_id)
-        .or_default()
-        .extend(new_orders.clone());
+fn no_open_session(table_id: u32) -> ApiError {
+    ApiError::Conflict(format!(
+        "Table {} has no open session; seat the guests first",
+        table_id
+    ))
+}
 
-    info!("Created orders for table: {:?}", payload.table_id);
+/// Portions each menu gives up: a set takes its own stock and its components'.
+fn portions_by_menu(lines: &[ResolvedLine<'_>]) -> HashMap<Uuid, u32> {
+    let mut portions = HashMap::new();
+    for line in lines {
+        *portions.entry(line.menu.id).or_insert(0) += line.quantity;
+        for portion in &line.portions {
+            *portions.entry(portion.menu.id).or_insert(0) += line.quantity;
+        }
+    }
+    portions
+}
 
-    let response = Json(json!({
-        "status": "success",
-        "data": new_orders
-    }));
+/// Point every line of a short menu at the shortage, so the tablet can grey them out.
+/// A set is short when any of its portions is.
+fn shortage_lines(lines: &[ResolvedLine<'_>], short: &[StockShortage]) -> Vec<InvalidLine> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let (menu, shortage) = std::iter::once(line.menu)
+                .chain(line.portions.iter().map(|portion| portion.menu))
+                .find_map(|menu| {
+                    let shortage = short.iter().find(|s| s.menu_id == menu.id)?;
+                    Some((menu, shortage))
+                })?;
+            let mut reason = if shortage.available == 0 {
+                format!("Menu '{}' is sold out", menu.name)
+            } else {
+                format!(
+                    "Only {} '{}' left, {} ordered",
+                    shortage.available, menu.name, shortage.requested
+                )
+            };
+            if menu.id != line.menu.id {
+                reason = format!("Menu '{}': {}", line.menu.name, reason);
+            }
+            Some(InvalidLine {
+                index,
+                menu_id: line.menu.id,
+                reason,
+            })
+        })
+        .collect()
+}
 
-    Ok(response)
+/// Match every requested line against the catalog. Unknown ids, names that don't
+/// belong to the id, menus outside their hours at `store_time`, quantities or
+/// notes over the limits and invalid modifier choices are collected so the client can fix all of them at once.
+fn resolve_lines<'a>(
+    catalog: &'a [Menu],
+    requested: &[OrderLineRequest],
+    store_time: NaiveTime,
+) -> Result<Vec<ResolvedLine<'a>>, Vec<InvalidLine>> {
+    let mut lines = Vec::with_capacity(requested.len());
+    let mut invalid = Vec::new();
+    for (index, request) in requested.iter().enumerate() {
+        let menu_id = request.menu_id;
+        let note = match request.checked_note() {
+            Ok(note) => note,
+            Err(reason) => {
+                invalid.push(InvalidLine {
+                    index,
+                    menu_id,
+                    reason,
+                });
+                continue;
+            }
+        };
+        match catalog.iter().find(|menu| menu.id == menu_id) {
+            Some(menu) if menu.retired => invalid.push(InvalidLine {
+                index,
+                menu_id,
+                reason: format!("Menu '{}' is no longer available", menu.name),
+            }),
+            Some(menu)
+                if request
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| !menu.answers_to(name)) =>
+            {
+                invalid.push(InvalidLine {
+                    index,
+                    menu_id,
+                    reason: format!(
+                        "Menu name '{}' does not match catalog name '{}'",
+                        request.name.as_deref().unwrap_or_default(),
+                        menu.name
+                    ),
+                })
+            }
+            Some(menu) if !menu.is_available_at(store_time) => invalid.push(InvalidLine {
+                index,
+                menu_id,
+                reason: format!(
+                    "Menu '{}' can only be ordered {}",
+                    menu.name,
+                    menu.availability_label()
+                ),
+            }),
+            Some(menu) => {
+                let resolved = if menu.is_combo() {
+                    resolve_portions(menu, catalog, &request.modifiers, store_time)
+                        .map(|portions| (Vec::new(), portions))
+                } else {
+                    select_modifiers(&menu.modifier_groups, &request.modifiers)
+                        .map(|modifiers| (modifiers, Vec::new()))
+                }
+                .map(|(modifiers, portions)| ResolvedLine {
+                    menu,
+                    quantity: request.quantity,
+                    note: note.clone(),
+                    modifiers,
+                    portions,
+                });
+                match resolved {
+                    Ok(resolved) => lines.push(resolved),
+                    Err(reason) => invalid.push(InvalidLine {
+                        index,
+                        menu_id,
+                        reason: format!("Menu '{}': {}", menu.name, reason),
+                    }),
+                }
+            }
+            None => invalid.push(InvalidLine {
+                index,
+                menu_id,
+                reason: "Unknown menu id".to_string(),
+            }),
+        }
+    }
+    if invalid.is_empty() {
+        Ok(lines)
+    } else {
+        Err(invalid)
+    }
 }
 
-fn random_cooking_time() -> u32 {
-    rand::rng().random_range(5..=15)
+/// A set's portions follow the rules of their own menus: each must be on sale at
+/// `store_time`, and the line's choices fill in the portions' modifier groups.
+/// Every portion of the same menu gets the same options.
+fn resolve_portions<'a>(
+    combo: &Menu,
+    catalog: &'a [Menu],
+    choices: &[ModifierChoice],
+    store_time: NaiveTime,
+) -> Result<Vec<Portion<'a>>, String> {
+    let menus = expand_combo(combo, catalog)?;
+    if let Some(menu) = menus.iter().find(|menu| !menu.is_available_at(store_time)) {
+        return Err(format!(
+            "'{}' can only be ordered {}",
+            menu.name,
+            menu.availability_label()
+        ));
+    }
+    let offers = |menu: &Menu, choice: &ModifierChoice| {
+        menu.modifier_groups
+            .iter()
+            .any(|group| group.id == choice.group_id)
+    };
+    if let Some(choice) = choices
+        .iter()
+        .find(|choice| !menus.iter().any(|menu| offers(menu, choice)))
+    {
+        return Err(format!("Unknown modifier group '{}'", choice.group_id));
+    }
+    menus
+        .into_iter()
+        .map(|menu| {
+            let own: Vec<ModifierChoice> = choices
+                .iter()
+                .filter(|choice| offers(menu, choice))
+                .cloned()
+                .collect();
+            let modifiers = select_modifiers(&menu.modifier_groups, &own)
+                .map_err(|reason| format!("'{}': {}", menu.name, reason))?;
+            Ok(Portion { menu, modifiers })
+        })
+        .collect()
+}
+
+/// Kitchen staff only have the ticket, so look the order up by its id alone.
+pub async fn get_order(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path(order_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    match state
+        .orders
+        .get_by_id(order_id)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(order) => {
+            info!("get_order: {:?}", order);
+            let data = OrderResponse::from(order).localized(&locales);
+            Ok(Json(json!({ "status": "success", "data": data })))
+        }
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+pub async fn get_ticket(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path(ticket_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let orders = state
+        .orders
+        .list_by_ticket(ticket_id)
+        .await
+        .map_err(internal_error)?;
+    match TicketResponse::group(orders, Utc::now(), &locales).pop() {
+        Some(ticket) => Ok(Json(json!({ "status": "success", "data": ticket }))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+pub async fn delete_order(
+    State(state): State<Arc<AppState>>,
+    Path(order_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    match state
+        .orders
+        .delete_by_id(order_id)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(order) => {
+            info!("deleting order {} on table {}", order_id, order.table_id);
+            if order.status.is_unfinished() {
+                give_back_stock(&state, &order).await;
+            }
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => Err(StatusCode::NOT_FOUND),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::order::model::MenuData;
+    use crate::menu::combo::ComboComponent;
+    use crate::menu::model::CookingTimeProfile;
+    use crate::menu::model::{Allergen, AvailabilityWindow, Price};
+    use crate::menu::modifier::{ModifierGroup, ModifierOption};
+    use crate::order::estimator::{CookingTimeEstimator, Estimate};
+    use crate::order::model::{MAX_LINE_QUANTITY, MAX_NOTE_LEN};
+    use crate::order::repository::RepositoryError;
+    use crate::session::repository::InMemorySessionRepository;
+    use async_trait::async_trait;
+    use chrono::DateTime;
 
     fn create_test_state() -> Arc<AppState> {
-        Arc::new(AppState::new())
+        Arc::new(seated_state())
     }
 
-    fn create_test_menu() -> MenuData {
-        MenuData {
-            id: Uuid::new_v4(),
-            name: "Test Menu".to_string(),
-        }
+    /// A party is seated at every configured table, so tests can order anywhere.
+    fn seated_state() -> AppState {
+        let seated = (1..=100)
+            .map(|table_id| TableSession::open(table_id, 2, Vec::new(), Utc::now()))
+            .collect();
+        AppState::new().with_session_storage(Arc::new(InMemorySessionRepository::new(seated)))
+    }
+
+    fn lines(menus: Vec<MenuData>) -> Vec<OrderLineRequest> {
+        menus.into_iter().map(OrderLineRequest::from).collect()
+    }
+
+    async fn create_test_menu(state: &AppState) -> MenuData {
+        catalog_menu(state, "Ramen").await
+    }
+
+    async fn catalog_menu(state: &AppState, name: &str) -> MenuData {
+        let menus = state.menus.list().await.unwrap();
+        let menu = menus.into_iter().find(|menu| menu.name == name).unwrap();
+        MenuData::from_menu(&menu)
     }
 
     #[tokio::test]
     async fn test_create_orders_success() {
         let state = create_test_state();
-        let menu = create_test_menu();
+        let menu = create_test_menu(&state).await;
 
         let payload = CreateOrderRequest {
             table_id: 1,
-            menus: vec![menu.clone()],
+            menus: lines(vec![menu.clone()]),
         };
 
-        let result = create_orders(State(state.clone()), Json(payload)).await;
+        let result = create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await;
 
         match result {
             Ok(response) => {
@@ -99,48 +508,77 @@ mod tests {
                 let data = json["data"].as_array().unwrap();
                 assert_eq!(data.len(), 1);
                 assert_eq!(data[0]["menu"]["name"], menu.name);
+                assert_eq!(data[0]["status"], "pending");
 
-                let orders = state.orders.read().await;
-                assert_eq!(orders.get(&1).unwrap().len(), 1);
+                let orders = state.orders.list_by_table(1).await.unwrap();
+                assert_eq!(orders.len(), 1);
             }
             Err(_) => panic!("Expected success response"),
         }
     }
 
+    #[tokio::test]
+    async fn test_orders_for_a_session_closed_meanwhile_are_refused() {
+        let state = create_test_state();
+        let session = state.sessions.active(1).await.unwrap().unwrap();
+        let catalog = state.menus.list().await.unwrap();
+        let now = Utc::now();
+        let requested = lines(vec![create_test_menu(&state).await]);
+        let resolved = resolve_lines(&catalog, &requested, store_time(now)).unwrap();
+
+        // The party left between the session check and the insert.
+        state.sessions.close(1, now).await.unwrap();
+        let error = place_orders(&state, &session, state.tickets.next(), &resolved, now)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        assert!(state.orders.list_by_table(1).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_create_orders_invalid_table() {
         let state = create_test_state();
         let payload = CreateOrderRequest {
             table_id: 99999, // Invalid table number
-            menus: vec![create_test_menu()],
+            menus: lines(vec![create_test_menu(&state).await]),
         };
 
-        let result = create_orders(State(state), Json(payload)).await;
+        let result = create_orders(State(state), PreferredLocales::default(), Json(payload)).await;
 
         match result {
             Ok(_) => panic!("Expected error response"),
-            Err(status) => assert_eq!(status, StatusCode::BAD_REQUEST),
+            Err(error) => assert_eq!(error.status(), StatusCode::BAD_REQUEST),
         }
     }
 
     #[tokio::test]
     async fn test_create_multiple_orders() {
         let state = create_test_state();
-        let menus = vec![create_test_menu(), create_test_menu(), create_test_menu()];
+        let menus = vec![
+            create_test_menu(&state).await,
+            create_test_menu(&state).await,
+            create_test_menu(&state).await,
+        ];
 
         let payload = CreateOrderRequest {
             table_id: 1,
-            menus: menus.clone(),
+            menus: lines(menus),
         };
 
-        let result = create_orders(State(state.clone()), Json(payload)).await;
+        let result = create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await;
 
         match result {
             Ok(response) => {
                 let response = response.into_response();
                 assert_eq!(response.status(), StatusCode::OK);
 
-                let body = axum::body::to_bytes(response.into_body(), 1024)
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
                     .await
                     .unwrap();
                 let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
@@ -148,8 +586,8 @@ mod tests {
                 let data = json["data"].as_array().unwrap();
                 assert_eq!(data.len(), 3);
 
-                let orders = state.orders.read().await;
-                assert_eq!(orders.get(&1).unwrap().len(), 3);
+                let orders = state.orders.list_by_table(1).await.unwrap();
+                assert_eq!(orders.len(), 3);
             }
             Err(_) => panic!("Expected success response"),
         }
@@ -158,15 +596,15 @@ mod tests {
     #[tokio::test]
     async fn test_order_timestamps() {
         let state = create_test_state();
-        let menu = create_test_menu();
+        let menu = create_test_menu(&state).await;
 
         let payload = CreateOrderRequest {
             table_id: 1,
-            menus: vec![menu],
+            menus: lines(vec![menu]),
         };
 
         let before = Utc::now();
-        let result = create_orders(State(state), Json(payload)).await;
+        let result = create_orders(State(state), PreferredLocales::default(), Json(payload)).await;
         let after = Utc::now();
 
         match result {
@@ -183,6 +621,20 @@ mod tests {
                 .unwrap();
 
                 assert!(created_at >= before && created_at <= after);
+
+                let ready_at = chrono::DateTime::parse_from_rfc3339(
+                    json["data"][0]["ready_at"].as_str().unwrap(),
+                )
+                .unwrap();
+                let cooking_time = json["data"][0]["cooking_time_minutes"].as_i64().unwrap();
+                assert_eq!(
+                    ready_at - created_at,
+                    chrono::Duration::minutes(cooking_time)
+                );
+                assert_eq!(
+                    json["data"][0]["remaining_seconds"].as_i64().unwrap(),
+                    cooking_time * 60
+                );
             }
             Err(_) => panic!("Expected success response"),
         }
@@ -196,7 +648,12 @@ mod tests {
             menus: vec![],
         };
 
-        let result = create_orders(State(state.clone()), Json(payload)).await;
+        let result = create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await;
 
         match result {
             Ok(response) => {
@@ -209,18 +666,816 @@ mod tests {
                 let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
                 assert!(json["data"].as_array().unwrap().is_empty());
 
-                let orders = state.orders.read().await;
-                assert!(orders.get(&1).unwrap_or(&vec![]).is_empty());
+                let orders = state.orders.list_by_table(1).await.unwrap();
+                assert!(orders.is_empty());
             }
             Err(_) => panic!("Expected success response"),
         }
     }
 
-    #[test]
-    fn test_random_cooking_time() {
-        for _ in 0..100 {
-            let cooking_time = random_cooking_time();
-            assert!((5..=15).contains(&cooking_time));
+    #[tokio::test]
+    async fn test_get_and_delete_order_by_id() {
+        let state = create_test_state();
+        let payload = CreateOrderRequest {
+            table_id: 42,
+            menus: lines(vec![create_test_menu(&state).await]),
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+        let order_id = state.orders.list_by_table(42).await.unwrap()[0].id;
+
+        let response = get_order(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path(order_id),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["table_id"], 42);
+
+        let response = delete_order(State(state.clone()), Path(order_id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(state.orders.list_by_table(42).await.unwrap().is_empty());
+
+        let response = get_order(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path(order_id),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = delete_order(State(state), Path(order_id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_each_request_gets_its_own_ticket() {
+        let state = create_test_state();
+        let ramen = create_test_menu(&state).await;
+        let beer = catalog_menu(&state, "Beer").await;
+        for menus in [vec![ramen.clone(), beer.clone()], vec![beer]] {
+            let payload = CreateOrderRequest {
+                table_id: 6,
+                menus: lines(menus),
+            };
+            create_orders(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Json(payload),
+            )
+            .await
+            .unwrap();
+        }
+
+        let orders = state.orders.list_by_table(6).await.unwrap();
+        let tickets: Vec<TicketRef> = orders.iter().map(|o| o.ticket.unwrap()).collect();
+        assert_eq!(tickets[0], tickets[1]);
+        assert_ne!(tickets[1].id, tickets[2].id);
+        assert_eq!((tickets[0].number, tickets[2].number), (1, 2));
+
+        let response = get_ticket(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path(tickets[0].id),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["number"], 1);
+        assert_eq!(json["data"]["table_id"], 6);
+        assert_eq!(json["data"]["items"].as_array().unwrap().len(), 2);
+        assert_eq!(json["data"]["items"][0]["menu"]["name"], ramen.name);
+
+        let response = get_ticket(
+            State(state),
+            PreferredLocales::default(),
+            Path(Uuid::new_v4()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cooking_time_follows_menu_profile() {
+        let state = create_test_state();
+        let beer = catalog_menu(&state, "Beer").await;
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![beer; 20]),
+        };
+
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert!(orders
+            .iter()
+            .all(|order| (1..=2).contains(&order.cooking_time_minutes)));
+    }
+
+    struct FixedCookingTime(u32);
+
+    #[async_trait]
+    impl CookingTimeEstimator for FixedCookingTime {
+        async fn estimate(
+            &self,
+            items: &[KitchenItem],
+            now: DateTime<Utc>,
+        ) -> Result<Vec<Estimate>, RepositoryError> {
+            Ok(items
+                .iter()
+                .map(|_| Estimate {
+                    cooking_time_minutes: self.0,
+                    ready_at: now + chrono::Duration::minutes(30),
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_estimator_is_pluggable() {
+        let state = Arc::new(seated_state().with_estimator(Arc::new(FixedCookingTime(7))));
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![create_test_menu(&state).await]),
+        };
+
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert_eq!(orders[0].cooking_time_minutes, 7);
+        assert_eq!(
+            orders[0].ready_at - orders[0].created_at,
+            chrono::Duration::minutes(30)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_rejects_invalid_lines() {
+        let state = create_test_state();
+        let unknown_id = Uuid::new_v4();
+        let mut wrong_name = create_test_menu(&state).await;
+        wrong_name.name = "Free sushi".to_string();
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![
+                create_test_menu(&state).await,
+                MenuData {
+                    id: unknown_id,
+                    name: "Ramen".to_string(),
+                    names: Default::default(),
+                },
+                wrong_name,
+            ]),
+        };
+
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = error.into_response();
+        let body = axum::body::to_bytes(response.into_body(), 2048)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let errors = json["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0]["index"], 1);
+        assert_eq!(errors[0]["menu_id"], unknown_id.to_string());
+        assert_eq!(errors[1]["index"], 2);
+
+        // Nothing is stored when any line is invalid.
+        assert!(state.orders.list_by_table(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_stores_canonical_name() {
+        let state = create_test_state();
+        let mut menu = create_test_menu(&state).await;
+        menu.name = "  rAMEN ".to_string();
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![menu]),
+        };
+
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert_eq!(orders[0].menu.name, "Ramen");
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_rejects_retired_menu() {
+        let state = create_test_state();
+        let ramen = create_test_menu(&state).await;
+        state.menus.retire(ramen.id).await.unwrap();
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![ramen]),
+        };
+
+        match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY),
+        }
+        assert!(state.orders.list_by_table(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unit_price_is_snapshot_at_creation() {
+        let state = create_test_state();
+        let ramen = create_test_menu(&state).await;
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![ramen.clone()]),
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let mut repriced = state.menus.get(ramen.id).await.unwrap().unwrap();
+        let original_price = repriced.price.clone();
+        repriced.price = Price::jpy(original_price.amount + 200);
+        assert!(state.menus.update(repriced).await.unwrap());
+
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert_eq!(orders[0].unit_price, original_price);
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_rejects_menu_outside_its_hours() {
+        let state = create_test_state();
+        let now = store_time(Utc::now());
+        let later = AvailabilityWindow::new(
+            now + chrono::Duration::hours(1),
+            now + chrono::Duration::hours(2),
+        );
+        let lunch = Menu::new(
+            "lunch-set",
+            "Lunch set",
+            Price::jpy(1200),
+            CookingTimeProfile::default(),
+        )
+        .with_availability(vec![later]);
+        state.menus.create(lunch.clone()).await.unwrap();
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![MenuData::from_menu(&lunch)]),
+        };
+
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(error.into_response().into_body(), 2048)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let reason = json["errors"][0]["reason"].as_str().unwrap();
+        assert!(reason.contains(&later.to_string()), "{}", reason);
+        assert!(state.orders.list_by_table(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_takes_stock_all_or_nothing() {
+        let state = create_test_state();
+        let beef = catalog_menu(&state, "Beef rice").await;
+        let beer = catalog_menu(&state, "Beer").await;
+        state.menus.set_stock(beef.id, Some(2)).await.unwrap();
+        state.menus.set_stock(beer.id, Some(5)).await.unwrap();
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![beer.clone(), beef.clone(), beef.clone(), beef.clone()]),
+        };
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(error.into_response().into_body(), 2048)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let errors = json["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0]["index"], 1);
+        assert!(state.orders.list_by_table(1).await.unwrap().is_empty());
+        let stock = state.menus.stock_levels().await.unwrap();
+        assert_eq!((stock[&beef.id], stock[&beer.id]), (2, 5));
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![beer.clone(), beef.clone(), beef.clone()]),
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+        let stock = state.menus.stock_levels().await.unwrap();
+        assert_eq!((stock[&beef.id], stock[&beer.id]), (0, 4));
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_with_modifiers() {
+        let state = create_test_state();
+        let ramen = create_test_menu(&state).await;
+        let choice = |group_id: &str, option_id: &str| ModifierChoice {
+            group_id: group_id.to_string(),
+            option_id: option_id.to_string(),
+        };
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: vec![OrderLineRequest {
+                modifiers: vec![
+                    choice("toppings", "chashu"),
+                    choice("firmness", "firm"),
+                    choice("toppings", "egg"),
+                ],
+                ..ramen.clone().into()
+            }],
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert_eq!(orders[0].unit_price, Price::jpy(980 + 250 + 100));
+        let picked: Vec<&str> = orders[0]
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.option_name.as_str())
+            .collect();
+        assert_eq!(picked, ["Firm", "Extra chashu", "Seasoned egg"]);
+
+        let payload = CreateOrderRequest {
+            table_id: 2,
+            menus: vec![
+                ramen.clone().into(),
+                OrderLineRequest {
+                    modifiers: vec![choice("firmness", "soft"), choice("firmness", "firm")],
+                    ..ramen.into()
+                },
+            ],
+        };
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(error.into_response().into_body(), 2048)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["errors"][0]["index"], 1);
+        let reason = json["errors"][0]["reason"].as_str().unwrap();
+        assert!(reason.contains("at most 1"), "{}", reason);
+        assert!(state.orders.list_by_table(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_orders_with_quantity_and_note() {
+        let state = create_test_state();
+        let beef = catalog_menu(&state, "Beef rice").await;
+        state.menus.set_stock(beef.id, Some(3)).await.unwrap();
+        let line = |quantity: u32, note: &str| OrderLineRequest {
+            quantity,
+            note: Some(note.to_string()),
+            ..beef.clone().into()
+        };
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: vec![
+                line(0, ""),
+                line(MAX_LINE_QUANTITY + 1, ""),
+                line(1, &"x".repeat(MAX_NOTE_LEN + 1)),
+                line(1, "no onion\nextra sauce"),
+                line(4, "no green onion"),
+            ],
+        };
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(error.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let indexes: Vec<i64> = json["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["index"].as_i64().unwrap())
+            .collect();
+        assert_eq!(indexes, [0, 1, 2, 3]);
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: vec![line(4, "no green onion")],
+        };
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: vec![line(3, "  no green onion  "), line(1, "   ")],
+        };
+        state.menus.set_stock(beef.id, Some(4)).await.unwrap();
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].quantity, 3);
+        assert_eq!(orders[0].note.as_deref(), Some("no green onion"));
+        assert_eq!(orders[0].unit_price, Price::jpy(750));
+        assert_eq!((orders[1].quantity, orders[1].note.as_deref()), (1, None));
+        let stock = state.menus.stock_levels().await.unwrap();
+        assert_eq!(stock[&beef.id], 0);
+    }
+
+    #[tokio::test]
+    async fn test_order_lines_accept_menu_id_or_legacy_shape() {
+        let line: OrderLineRequest = serde_json::from_value(serde_json::json!({
+            "menu_id": Uuid::nil(),
+            "quantity": 2,
+            "note": "no green onion",
+        }))
+        .unwrap();
+        assert_eq!((line.quantity, line.name), (2, None));
+        let line: OrderLineRequest =
+            serde_json::from_value(serde_json::json!({"id": Uuid::nil(), "name": "Ramen"}))
+                .unwrap();
+        assert_eq!((line.quantity, line.name.as_deref()), (1, Some("Ramen")));
+    }
+
+    #[tokio::test]
+    async fn test_declared_allergens_flag_order_lines() {
+        let state = create_test_state();
+        state
+            .sessions
+            .update(
+                3,
+                Box::new(|session| session.allergens = vec![Allergen::Alcohol, Allergen::Egg]),
+            )
+            .await
+            .unwrap();
+        let payload = CreateOrderRequest {
+            table_id: 3,
+            menus: lines(vec![
+                create_test_menu(&state).await,
+                catalog_menu(&state, "Beef rice").await,
+            ]),
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let orders = state.orders.list_by_table(3).await.unwrap();
+        let ramen = orders.iter().find(|o| o.menu.name == "Ramen").unwrap();
+        assert_eq!(ramen.allergen_alerts, [Allergen::Egg]);
+        let beef = orders.iter().find(|o| o.menu.name == "Beef rice").unwrap();
+        assert!(beef.allergen_alerts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_combo_expands_into_kitchen_orders() {
+        let state = Arc::new(seated_state().with_estimator(Arc::new(FixedCookingTime(4))));
+        let set = Menu::new(
+            "ramen-beer",
+            "Ramen + Beer set",
+            Price::jpy(1380),
+            CookingTimeProfile::default(),
+        )
+        .with_components(vec![
+            ComboComponent::new("ramen", 1),
+            ComboComponent::new("beer", 1),
+        ]);
+        state.menus.create(set.clone()).await.unwrap();
+        let set_line = MenuData::from_menu(&set);
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![set_line.clone(), set_line.clone()]),
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert_eq!(orders.len(), 4);
+        let mut line_ids: Vec<Uuid> = orders
+            .iter()
+            .map(|order| order.combo.as_ref().unwrap().line_id)
+            .collect();
+        line_ids.dedup();
+        assert_eq!(line_ids.len(), 2);
+        let first_set: Vec<&Order> = orders
+            .iter()
+            .filter(|order| order.combo.as_ref().unwrap().line_id == line_ids[0])
+            .collect();
+        let names: Vec<&str> = first_set.iter().map(|o| o.menu.name.as_str()).collect();
+        assert_eq!(names, ["Ramen", "Beer"]);
+        for order in first_set {
+            let combo = order.combo.as_ref().unwrap();
+            assert_eq!(combo.menu.name, "Ramen + Beer set");
+            assert_eq!(combo.unit_price, Price::jpy(1380));
+            assert_eq!(order.unit_price.amount, 0);
+            assert_eq!(order.cooking_time_minutes, 4);
         }
+
+        // A set is short as soon as one of its portions is.
+        let beer = catalog_menu(&state, "Beer").await;
+        state.menus.set_stock(beer.id, Some(1)).await.unwrap();
+        let payload = CreateOrderRequest {
+            table_id: 2,
+            menus: lines(vec![set_line.clone(), set_line]),
+        };
+        let error = match create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        {
+            Ok(_) => panic!("Expected error response"),
+            Err(error) => error,
+        };
+        let body = axum::body::to_bytes(error.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["errors"][0]["menu_id"], set.id.to_string());
+        let reason = json["errors"][0]["reason"].as_str().unwrap();
+        assert!(
+            reason.starts_with("Menu 'Ramen + Beer set': Only 1 'Beer'"),
+            "{}",
+            reason
+        );
+    }
+
+    #[tokio::test]
+    async fn test_combo_portions_follow_component_rules() {
+        let state = create_test_state();
+        let tea = Menu::new(
+            "tea",
+            "Tea",
+            Price::jpy(300),
+            CookingTimeProfile::uniform(1, 2),
+        )
+        .with_modifier_groups(vec![ModifierGroup {
+            required: true,
+            min_selections: 1,
+            ..ModifierGroup::optional(
+                "temperature",
+                "Temperature",
+                1,
+                vec![
+                    ModifierOption::new("hot", "Hot", 0),
+                    ModifierOption::new("iced", "Iced", 50),
+                ],
+            )
+        }]);
+        let set = Menu::new(
+            "ramen-tea",
+            "Ramen + Tea set",
+            Price::jpy(1180),
+            CookingTimeProfile::default(),
+        )
+        .with_components(vec![
+            ComboComponent::new("ramen", 1),
+            ComboComponent::new("tea", 1),
+        ]);
+        state.menus.create(tea.clone()).await.unwrap();
+        state.menus.create(set.clone()).await.unwrap();
+        let set_line = || OrderLineRequest::from(MenuData::from_menu(&set));
+        let choice = |group_id: &str, option_id: &str| ModifierChoice {
+            group_id: group_id.to_string(),
+            option_id: option_id.to_string(),
+        };
+        let reasons_of = |error: ApiError| match error {
+            ApiError::InvalidLines(errors) => errors
+                .into_iter()
+                .map(|line| line.reason)
+                .collect::<Vec<_>>(),
+            other => panic!("Expected invalid lines, got {:?}", other),
+        };
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: vec![set_line()],
+        };
+        let error = create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .err()
+        .unwrap();
+        let reasons = reasons_of(error);
+        assert!(reasons[0].contains("Temperature"), "{:?}", reasons);
+
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: vec![OrderLineRequest {
+                modifiers: vec![choice("temperature", "iced"), choice("firmness", "firm")],
+                ..set_line()
+            }],
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .unwrap();
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        let tea_order = orders.iter().find(|o| o.menu.name == "Tea").unwrap();
+        assert_eq!(tea_order.modifiers[0].option_id, "iced");
+        assert_eq!(tea_order.unit_price, Price::jpy(50));
+        let ramen_order = orders.iter().find(|o| o.menu.name == "Ramen").unwrap();
+        assert_eq!(ramen_order.modifiers[0].option_id, "firm");
+        assert_eq!(ramen_order.unit_price, Price::jpy(0));
+
+        // A portion outside its hours keeps the whole set off the ticket.
+        let now = store_time(Utc::now());
+        let later = AvailabilityWindow::new(
+            now + chrono::Duration::hours(1),
+            now + chrono::Duration::hours(2),
+        );
+        state
+            .menus
+            .update(tea.with_availability(vec![later]))
+            .await
+            .unwrap();
+        let payload = CreateOrderRequest {
+            table_id: 2,
+            menus: vec![OrderLineRequest {
+                modifiers: vec![choice("temperature", "hot")],
+                ..set_line()
+            }],
+        };
+        let error = create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .err()
+        .unwrap();
+        let reasons = reasons_of(error);
+        assert!(reasons[0].contains(&later.to_string()), "{:?}", reasons);
+    }
+
+    #[tokio::test]
+    async fn test_order_names_follow_requested_locale() {
+        let state = create_test_state();
+        let mut ramen = create_test_menu(&state).await;
+        // Tablets showing Japanese send the Japanese name back.
+        ramen.name = "ラーメン".to_string();
+        let payload = CreateOrderRequest {
+            table_id: 1,
+            menus: lines(vec![ramen]),
+        };
+        let response = create_orders(
+            State(state.clone()),
+            PreferredLocales::from_request(Some("ja"), None),
+            Json(payload),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"][0]["menu"]["name"], "ラーメン");
+
+        let order = &state.orders.list_by_table(1).await.unwrap()[0];
+        assert_eq!(order.menu.name, "Ramen");
+        let response = get_order(
+            State(state.clone()),
+            PreferredLocales::from_request(None, Some("en-US")),
+            Path(order.id),
+        )
+        .await
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["menu"]["name"], "Ramen");
+        assert_eq!(json["data"]["menu"]["names"]["ja"], "ラーメン");
     }
 }
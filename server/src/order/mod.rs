@@ -1,2 +1,6 @@
+pub mod countdown;
+pub mod estimator;
 pub mod handler;
 pub mod model;
+pub mod repository;
+pub mod ticket;
@@ -1,24 +1,506 @@
-use chrono::{DateTime, Utc};
+use crate::locale::PreferredLocales;
+use crate::menu::model::{Allergen, Menu, Price};
+use crate::menu::modifier::{ModifierChoice, SelectedModifier};
+use crate::order::ticket::TicketRef;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MenuData {
     pub id: Uuid,
     pub name: String,
+    /// The catalog's names in other locales when the order was placed, so
+    /// responses can follow the guest's language.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub names: BTreeMap<String, String>,
 }
 
+/// Most portions one order line may ask for; bigger parties send more lines.
+pub const MAX_LINE_QUANTITY: u32 = 20;
+/// Longest kitchen note, in characters. It has to fit on a ticket.
+pub const MAX_NOTE_LEN: usize = 140;
+
+/// The set meal a kitchen order was expanded from. All portions of one ordered
+/// set share `line_id`, which is what the bill shows as a single line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComboLine {
+    pub line_id: Uuid,
+    pub menu: MenuData,
+    pub unit_price: Price,
+}
+
+/// Lifecycle of an order in the kitchen. Served and Cancelled are terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    #[default]
+    Pending,
+    Cooking,
+    Ready,
+    Served,
+    Cancelled,
+}
+
+/// Older stored orders lack some fields; `StoredOrder` fills them in on read.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "StoredOrder")]
 pub struct Order {
     pub id: Uuid,
     pub table_id: u32,
+    /// The create request this order was placed with.
+    pub ticket: Option<TicketRef>,
+    /// The table session of the party that ordered it.
+    pub session_id: Option<Uuid>,
     pub menu: MenuData,
+    /// The menu's price plus its modifiers when the order was placed. Later price
+    /// changes leave it alone.
+    pub unit_price: Price,
+    pub quantity: u32,
+    pub note: Option<String>,
+    pub modifiers: Vec<SelectedModifier>,
+    /// Allergens the table declared that this dish contains, so staff can double check.
+    pub allergen_alerts: Vec<Allergen>,
+    /// Set when this dish is one portion of an ordered set meal. A portion's
+    /// `unit_price` is only its modifiers; the set is billed once through its combo line.
+    pub combo: Option<Box<ComboLine>>,
+    /// Changes made after the order was placed, oldest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub amendments: Vec<Amendment>,
     pub cooking_time_minutes: u32,
     pub created_at: DateTime<Utc>,
+    /// When the kitchen is expected to have the dish ready.
+    pub ready_at: DateTime<Utc>,
+    pub status: OrderStatus,
+}
+
+/// How an `Order` is read back. Orders stored before `ready_at` existed get
+/// the estimate they would have had: created plus cooking time.
+#[derive(Deserialize)]
+struct StoredOrder {
+    id: Uuid,
+    table_id: u32,
+    #[serde(default)]
+    ticket: Option<TicketRef>,
+    #[serde(default)]
+    session_id: Option<Uuid>,
+    menu: MenuData,
+    #[serde(default)]
+    unit_price: Price,
+    #[serde(default = "one")]
+    quantity: u32,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    modifiers: Vec<SelectedModifier>,
+    #[serde(default)]
+    allergen_alerts: Vec<Allergen>,
+    #[serde(default)]
+    combo: Option<Box<ComboLine>>,
+    #[serde(default)]
+    amendments: Vec<Amendment>,
+    cooking_time_minutes: u32,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    ready_at: Option<DateTime<Utc>>,
+    // Orders persisted before statuses existed are still waiting in the queue.
+    #[serde(default)]
+    status: OrderStatus,
+}
+
+impl From<StoredOrder> for Order {
+    fn from(stored: StoredOrder) -> Self {
+        Order {
+            id: stored.id,
+            table_id: stored.table_id,
+            ticket: stored.ticket,
+            session_id: stored.session_id,
+            menu: stored.menu,
+            unit_price: stored.unit_price,
+            quantity: stored.quantity,
+            note: stored.note,
+            modifiers: stored.modifiers,
+            allergen_alerts: stored.allergen_alerts,
+            combo: stored.combo,
+            amendments: stored.amendments,
+            cooking_time_minutes: stored.cooking_time_minutes,
+            created_at: stored.created_at,
+            ready_at: stored.ready_at.unwrap_or_else(|| {
+                Order::ready_at_for(stored.created_at, stored.cooking_time_minutes)
+            }),
+            status: stored.status,
+        }
+    }
+}
+
+/// What clients see for an order: the stored order plus its live countdown.
+#[derive(Debug, Serialize)]
+pub struct OrderResponse {
+    #[serde(flatten)]
+    pub order: Order,
+    pub remaining_seconds: i64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateOrderRequest {
     pub table_id: u32,
-    pub menus: Vec<MenuData>,
+    pub menus: Vec<OrderLineRequest>,
+}
+
+/// One line of a create request: which menu, how many, a note for the kitchen
+/// and the options the guest picked. `{"id", "name"}` lines from older tablets
+/// still parse as one portion.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderLineRequest {
+    #[serde(alias = "id")]
+    pub menu_id: Uuid,
+    /// When sent, it must name the same menu, which catches stale tablet menus.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "one")]
+    pub quantity: u32,
+    /// Free text for the kitchen, e.g. "no green onion".
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub modifiers: Vec<ModifierChoice>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOrderStatusRequest {
+    pub status: OrderStatus,
+}
+
+/// Body of `PATCH /tables/:id/orders/:order_id`. Fields left out keep their
+/// value; an empty `note` clears it.
+#[derive(Debug, Default, Deserialize)]
+pub struct AmendOrderRequest {
+    pub quantity: Option<u32>,
+    pub note: Option<String>,
+    pub modifiers: Option<Vec<ModifierChoice>>,
+}
+
+/// The part of an order the guest can still change before cooking starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineDetails {
+    pub quantity: u32,
+    pub note: Option<String>,
+    pub modifiers: Vec<SelectedModifier>,
+}
+
+/// One change to a placed order, kept so staff can see what the guest asked for first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Amendment {
+    pub amended_at: DateTime<Utc>,
+    pub before: LineDetails,
+    pub after: LineDetails,
+}
+
+/// One rejected line of a create request. `index` points into the request's `menus`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvalidLine {
+    pub index: usize,
+    pub menu_id: Uuid,
+    pub reason: String,
+}
+
+/// Why a change to an existing order was refused.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OrderUpdateError {
+    #[error("Order is already {0} and can no longer change")]
+    Finished(OrderStatus),
+    #[error("Order cannot move from {from} to {to}")]
+    InvalidTransition { from: OrderStatus, to: OrderStatus },
+    #[error("Order is not due to be ready yet")]
+    NotDue,
+    #[error("Order is already {0}; only pending orders can be amended")]
+    NotAmendable(OrderStatus),
+    #[error("Order is a portion of a set meal; only its note can be amended")]
+    PartOfSet,
+    #[error("Order changed while it was being amended; try again")]
+    Changed,
+}
+
+impl OrderStatus {
+    pub const ALL: [OrderStatus; 5] = [
+        OrderStatus::Pending,
+        OrderStatus::Cooking,
+        OrderStatus::Ready,
+        OrderStatus::Served,
+        OrderStatus::Cancelled,
+    ];
+
+    pub fn is_terminal(self) -> bool {
+        matches!(self, OrderStatus::Served | OrderStatus::Cancelled)
+    }
+
+    /// Still waiting for the kitchen, i.e. counts towards the cooking backlog.
+    pub fn is_unfinished(self) -> bool {
+        matches!(self, OrderStatus::Pending | OrderStatus::Cooking)
+    }
+
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Cooking)
+                | (Pending, Cancelled)
+                | (Cooking, Ready)
+                | (Cooking, Cancelled)
+                | (Ready, Served)
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Cooking => "cooking",
+            OrderStatus::Ready => "ready",
+            OrderStatus::Served => "served",
+            OrderStatus::Cancelled => "cancelled",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Order {
+    pub fn ready_at_for(created_at: DateTime<Utc>, cooking_time_minutes: u32) -> DateTime<Utc> {
+        created_at + Duration::minutes(i64::from(cooking_time_minutes))
+    }
+
+    /// Seconds until `ready_at`, or 0 once the dish is done (or will never be).
+    pub fn remaining_seconds(&self, now: DateTime<Utc>) -> i64 {
+        if !self.status.is_unfinished() {
+            return 0;
+        }
+        (self.ready_at - now).num_seconds().max(0)
+    }
+
+    /// The countdown starts at creation, so once it elapses the dish is considered
+    /// ready whether or not staff marked it as cooking. A pending order passes through
+    /// cooking on the way, the same transitions staff would make.
+    pub fn mark_ready_if_due(&mut self, now: DateTime<Utc>) -> Result<(), OrderUpdateError> {
+        if !self.status.is_unfinished() || self.ready_at > now {
+            return Err(OrderUpdateError::NotDue);
+        }
+        if self.status == OrderStatus::Pending {
+            self.transition_to(OrderStatus::Cooking)?;
+        }
+        self.transition_to(OrderStatus::Ready)
+    }
+
+    pub fn transition_to(&mut self, next: OrderStatus) -> Result<(), OrderUpdateError> {
+        if self.status.is_terminal() {
+            return Err(OrderUpdateError::Finished(self.status));
+        }
+        if !self.status.can_transition_to(next) {
+            return Err(OrderUpdateError::InvalidTransition {
+                from: self.status,
+                to: next,
+            });
+        }
+        self.status = next;
+        Ok(())
+    }
+
+    pub fn details(&self) -> LineDetails {
+        LineDetails {
+            quantity: self.quantity,
+            note: self.note.clone(),
+            modifiers: self.modifiers.clone(),
+        }
+    }
+
+    /// Replace the details of an order the kitchen has not started, as long as
+    /// they still read `expected`. The price follows the new modifiers and the
+    /// change is added to `amendments`; the order keeps its place in the queue.
+    pub fn amend(
+        &mut self,
+        expected: &LineDetails,
+        after: LineDetails,
+        at: DateTime<Utc>,
+    ) -> Result<(), OrderUpdateError> {
+        if self.status != OrderStatus::Pending {
+            return Err(OrderUpdateError::NotAmendable(self.status));
+        }
+        let before = self.details();
+        if &before != expected {
+            return Err(OrderUpdateError::Changed);
+        }
+        if before == after {
+            return Ok(());
+        }
+        if self.combo.is_some()
+            && (before.quantity != after.quantity || before.modifiers != after.modifiers)
+        {
+            return Err(OrderUpdateError::PartOfSet);
+        }
+        let delta: i64 = before.modifiers.iter().map(|m| m.price_delta).sum();
+        let base = Price::new(
+            self.unit_price.amount.saturating_add_signed(-delta),
+            &self.unit_price.currency,
+        );
+        self.unit_price = base.with_modifiers(&after.modifiers);
+        self.quantity = after.quantity;
+        self.note = after.note.clone();
+        self.modifiers = after.modifiers.clone();
+        self.amendments.push(Amendment {
+            amended_at: at,
+            before,
+            after,
+        });
+        Ok(())
+    }
+}
+
+impl MenuData {
+    /// Always the catalog's canonical name, never what the tablet sent.
+    pub fn from_menu(menu: &Menu) -> Self {
+        Self {
+            id: menu.id,
+            name: menu.name.clone(),
+            names: menu.names.clone(),
+        }
+    }
+
+    fn localize(&mut self, locales: &PreferredLocales) {
+        self.name = locales.pick(&self.names, &self.name).to_string();
+    }
+}
+
+impl OrderLineRequest {
+    /// Check the limits on quantity and note, returning the note as stored.
+    pub fn checked_note(&self) -> Result<Option<String>, String> {
+        check_quantity(self.quantity)?;
+        normalize_note(self.note.as_deref())
+    }
+}
+
+pub fn check_quantity(quantity: u32) -> Result<(), String> {
+    if !(1..=MAX_LINE_QUANTITY).contains(&quantity) {
+        return Err(format!("Quantity must be 1-{}", MAX_LINE_QUANTITY));
+    }
+    Ok(())
+}
+
+/// A kitchen note as stored: trimmed, and `None` when blank.
+pub fn normalize_note(note: Option<&str>) -> Result<Option<String>, String> {
+    let Some(note) = note.map(str::trim).filter(|n| !n.is_empty()) else {
+        return Ok(None);
+    };
+    if note.chars().count() > MAX_NOTE_LEN {
+        return Err(format!("Note must be at most {} characters", MAX_NOTE_LEN));
+    }
+    if note.chars().any(char::is_control) {
+        return Err("Note must be a single line of text".to_string());
+    }
+    Ok(Some(note.to_string()))
+}
+
+impl From<MenuData> for OrderLineRequest {
+    fn from(menu: MenuData) -> Self {
+        Self {
+            menu_id: menu.id,
+            name: Some(menu.name),
+            quantity: 1,
+            note: None,
+            modifiers: Vec::new(),
+        }
+    }
+}
+
+impl OrderResponse {
+    pub fn at(order: Order, now: DateTime<Utc>) -> Self {
+        let remaining_seconds = order.remaining_seconds(now);
+        Self {
+            order,
+            remaining_seconds,
+        }
+    }
+
+    /// Menu names in the guest's language. The stored order keeps the canonical name.
+    pub fn localized(mut self, locales: &PreferredLocales) -> Self {
+        self.order.menu.localize(locales);
+        if let Some(combo) = self.order.combo.as_mut() {
+            combo.menu.localize(locales);
+        }
+        self
+    }
+}
+
+impl From<Order> for OrderResponse {
+    fn from(order: Order) -> Self {
+        Self::at(order, Utc::now())
+    }
+}
+
+/// Fixture shared by the test modules: a pending, unticketed 10 minute order.
+#[cfg(test)]
+impl Order {
+    pub fn for_test(table_id: u32) -> Self {
+        let created_at = Utc::now();
+        Order {
+            id: Uuid::new_v4(),
+            table_id,
+            ticket: None,
+            session_id: None,
+            menu: MenuData {
+                id: Uuid::new_v4(),
+                name: "Test Menu".to_string(),
+                names: Default::default(),
+            },
+            unit_price: Price::jpy(980),
+            quantity: 1,
+            note: None,
+            modifiers: Vec::new(),
+            allergen_alerts: Vec::new(),
+            combo: None,
+            amendments: Vec::new(),
+            cooking_time_minutes: 10,
+            created_at,
+            ready_at: Order::ready_at_for(created_at, 10),
+            status: OrderStatus::Pending,
+        }
+    }
+
+    /// Placed at `created_at` with its countdown starting right away.
+    pub fn cooked_from(self, created_at: DateTime<Utc>, cooking_time_minutes: u32) -> Self {
+        Order {
+            cooking_time_minutes,
+            created_at,
+            ready_at: Order::ready_at_for(created_at, cooking_time_minutes),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_order_stored_before_ready_at_still_decodes() {
+        let created_at: DateTime<Utc> = "2024-05-01T12:00:00Z".parse().unwrap();
+        let order: Order = serde_json::from_value(json!({
+            "id": Uuid::new_v4(),
+            "table_id": 3,
+            "menu": {"id": Uuid::new_v4(), "name": "Ramen"},
+            "cooking_time_minutes": 12,
+            "created_at": created_at,
+        }))
+        .unwrap();
+        assert_eq!(order.ready_at, created_at + Duration::minutes(12));
+        assert_eq!(order.quantity, 1);
+        assert_eq!(order.status, OrderStatus::Pending);
+    }
 }
@@ -0,0 +1,633 @@
+use crate::order::model::{Order, OrderStatus};
+use crate::order::repository::{
+    InMemoryOrderRepository, OrderRepository, OrderUpdate, RepositoryError, UpdateOutcome,
+};
+use crate::order::ticket::TicketRef;
+use crate::storage::file::write_atomically;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+const JOURNAL_FILE: &str = "orders.journal";
+const SNAPSHOT_FILE: &str = "orders.snapshot";
+const ARCHIVE_SNAPSHOT_FILE: &str = "archived.snapshot";
+
+/// One line of the journal. Replaying an entry twice must be harmless, because a crash
+/// between writing a snapshot and truncating the journal leaves both on disk.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalEntry {
+    Create {
+        orders: Vec<Order>,
+    },
+    /// Full state of the order after the change, so replay never re-runs business rules.
+    Update {
+        order: Box<Order>,
+    },
+    Delete {
+        table_id: u32,
+        order_id: Uuid,
+    },
+    /// The orders a table clear took off, by id, so replay touches nothing newer.
+    Clear {
+        table_id: u32,
+        order_ids: Vec<Uuid>,
+        archive: bool,
+    },
+}
+
+/// In-memory store that survives restarts. Every mutation is appended (and synced) to
+/// the journal before it is applied, and a timer periodically compacts the journal
+/// into a snapshot of the whole store.
+pub struct JournaledOrderRepository {
+    inner: InMemoryOrderRepository,
+    // Held across "append + apply" so the journal order always matches the memory order.
+    journal: Mutex<File>,
+    dir: PathBuf,
+}
+
+impl JournaledOrderRepository {
+    /// Rebuild the store from `dir`: load the snapshot, then replay the journal tail.
+    pub async fn open(dir: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        let inner = InMemoryOrderRepository::new();
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        if snapshot_path.exists() {
+            let orders: Vec<Order> = serde_json::from_slice(&std::fs::read(&snapshot_path)?)?;
+            info!("Loaded {} orders from snapshot", orders.len());
+            inner.create(orders).await?;
+        }
+        let archive_path = dir.join(ARCHIVE_SNAPSHOT_FILE);
+        if archive_path.exists() {
+            let archived: Vec<Order> = serde_json::from_slice(&std::fs::read(&archive_path)?)?;
+            info!("Loaded {} archived orders from snapshot", archived.len());
+            inner.restore_archived(archived).await;
+        }
+
+        let journal_path = dir.join(JOURNAL_FILE);
+        let mut journal = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&journal_path)?;
+        let content = std::fs::read_to_string(&journal_path)?;
+        let (replayed, valid_len) = replay(&inner, &content).await?;
+        if valid_len < content.len() {
+            journal.set_len(valid_len as u64)?;
+        }
+        if valid_len > 0 && !content[..valid_len].ends_with('\n') {
+            std::io::Write::write_all(&mut journal, b"\n")?;
+        }
+        info!("Replayed {} journal entries", replayed);
+
+        Ok(Self {
+            inner,
+            journal: Mutex::new(File::from_std(journal)),
+            dir,
+        })
+    }
+
+    /// Write the whole store to a fresh snapshot and truncate the journal.
+    pub async fn snapshot(&self) -> Result<(), RepositoryError> {
+        let journal = self.journal.lock().await;
+        let orders = self.inner.all_orders().await;
+        let archived = self.inner.all_archived().await;
+
+        // The archive goes first: replaying a clear into an already archived order is
+        // skipped, while a lost archive could not be rebuilt.
+        write_atomically(&self.dir.join(ARCHIVE_SNAPSHOT_FILE), &archived).await?;
+        write_atomically(&self.dir.join(SNAPSHOT_FILE), &orders).await?;
+
+        journal.set_len(0).await?;
+        journal.sync_all().await?;
+        info!("Snapshot written with {} orders", orders.len());
+        Ok(())
+    }
+
+    /// Ids of the table's orders that match. Called with the journal lock held, so
+    /// the clear entry written next names exactly what the inner store removes.
+    async fn matching_ids(
+        &self,
+        table_id: u32,
+        matches: impl Fn(&Order) -> bool,
+    ) -> Result<Vec<Uuid>, RepositoryError> {
+        Ok(self
+            .inner
+            .list_by_table(table_id)
+            .await?
+            .into_iter()
+            .filter(|order| matches(order))
+            .map(|order| order.id)
+            .collect())
+    }
+
+    pub fn spawn_snapshots(self: Arc<Self>, every: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            // The first tick completes immediately and there is nothing new to compact yet.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.snapshot().await {
+                    error!("Failed to write order snapshot: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Write `content` to `dir/name` through a synced temp file, so readers see either
+/// the old file or the new one.
+async fn append(journal: &mut File, entry: &JournalEntry) -> Result<(), RepositoryError> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    journal.write_all(&line).await?;
+    journal.sync_data().await?;
+    Ok(())
+}
+
+fn overwrite(order: Order) -> OrderUpdate {
+    Box::new(move |stored| {
+        *stored = order;
+        Ok(())
+    })
+}
+
+/// Apply every journal entry to `inner`. Returns how many entries were replayed and the
+/// byte length of the intact prefix, so a torn tail can be cut off before appending again.
+async fn replay(
+    inner: &InMemoryOrderRepository,
+    journal: &str,
+) -> Result<(usize, usize), RepositoryError> {
+    let lines: Vec<&str> = journal.split_inclusive('\n').collect();
+    let mut replayed = 0;
+    let mut valid_len = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            valid_len += line.len();
+            continue;
+        }
+        let entry = match serde_json::from_str::<JournalEntry>(line) {
+            Ok(entry) => entry,
+            // A crash mid-append can only tear the very last line.
+            Err(e) if index + 1 == lines.len() => {
+                warn!("Ignoring torn journal tail: {}", e);
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        match entry {
+            JournalEntry::Create { orders } => {
+                let mut fresh = Vec::with_capacity(orders.len());
+                for order in orders {
+                    if inner.get(order.table_id, order.id).await?.is_none() {
+                        fresh.push(order);
+                    }
+                }
+                inner.create(fresh).await?;
+            }
+            JournalEntry::Update { order } => {
+                inner
+                    .update(order.table_id, order.id, overwrite(*order))
+                    .await?;
+            }
+            JournalEntry::Delete { table_id, order_id } => {
+                inner.delete(table_id, order_id).await?;
+            }
+            JournalEntry::Clear {
+                table_id,
+                order_ids,
+                archive,
+            } => {
+                let archived = inner.list_archived(table_id).await?;
+                for order_id in order_ids {
+                    let Some(order) = inner.get(table_id, order_id).await? else {
+                        continue;
+                    };
+                    inner.delete(table_id, order_id).await?;
+                    if archive && !archived.iter().any(|a| a.id == order_id) {
+                        inner.restore_archived(vec![order]).await;
+                    }
+                }
+            }
+        }
+        replayed += 1;
+        valid_len += line.len();
+    }
+    Ok((replayed, valid_len))
+}
+
+#[async_trait]
+impl OrderRepository for JournaledOrderRepository {
+    async fn create(&self, orders: Vec<Order>) -> Result<(), RepositoryError> {
+        let mut journal = self.journal.lock().await;
+        append(
+            &mut journal,
+            &JournalEntry::Create {
+                orders: orders.clone(),
+            },
+        )
+        .await?;
+        self.inner.create(orders).await
+    }
+
+    async fn list_by_table(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+        self.inner.list_by_table(table_id).await
+    }
+
+    async fn list_by_status(
+        &self,
+        statuses: &[OrderStatus],
+    ) -> Result<Vec<Order>, RepositoryError> {
+        self.inner.list_by_status(statuses).await
+    }
+
+    async fn list_by_ticket(&self, ticket_id: Uuid) -> Result<Vec<Order>, RepositoryError> {
+        self.inner.list_by_ticket(ticket_id).await
+    }
+
+    async fn latest_ticket(&self) -> Result<Option<TicketRef>, RepositoryError> {
+        self.inner.latest_ticket().await
+    }
+
+    async fn get(&self, table_id: u32, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        self.inner.get(table_id, order_id).await
+    }
+
+    async fn get_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        self.inner.get_by_id(order_id).await
+    }
+
+    async fn update(
+        &self,
+        table_id: u32,
+        order_id: Uuid,
+        apply: OrderUpdate,
+    ) -> Result<UpdateOutcome, RepositoryError> {
+        let mut journal = self.journal.lock().await;
+        // Decide on a copy first: only a change that is going to be applied gets journaled.
+        let Some(mut order) = self.inner.get(table_id, order_id).await? else {
+            return Ok(UpdateOutcome::NotFound);
+        };
+        if let Err(reason) = apply(&mut order) {
+            return Ok(UpdateOutcome::Rejected(reason));
+        }
+        append(
+            &mut journal,
+            &JournalEntry::Update {
+                order: Box::new(order.clone()),
+            },
+        )
+        .await?;
+        self.inner
+            .update(table_id, order_id, overwrite(order))
+            .await
+    }
+
+    async fn delete(&self, table_id: u32, order_id: Uuid) -> Result<bool, RepositoryError> {
+        let mut journal = self.journal.lock().await;
+        if self.inner.get(table_id, order_id).await?.is_none() {
+            return Ok(false);
+        }
+        append(&mut journal, &JournalEntry::Delete { table_id, order_id }).await?;
+        self.inner.delete(table_id, order_id).await
+    }
+
+    async fn delete_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        let mut journal = self.journal.lock().await;
+        let Some(order) = self.inner.get_by_id(order_id).await? else {
+            return Ok(None);
+        };
+        let table_id = order.table_id;
+        append(&mut journal, &JournalEntry::Delete { table_id, order_id }).await?;
+        self.inner.delete_by_id(order_id).await
+    }
+
+    async fn clear_table(
+        &self,
+        table_id: u32,
+        statuses: &[OrderStatus],
+        archive: bool,
+    ) -> Result<Vec<Order>, RepositoryError> {
+        let mut journal = self.journal.lock().await;
+        let order_ids = self
+            .matching_ids(table_id, |order| statuses.contains(&order.status))
+            .await?;
+        if order_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        append(
+            &mut journal,
+            &JournalEntry::Clear {
+                table_id,
+                order_ids,
+                archive,
+            },
+        )
+        .await?;
+        self.inner.clear_table(table_id, statuses, archive).await
+    }
+
+    async fn archive_session(
+        &self,
+        table_id: u32,
+        session_id: Uuid,
+    ) -> Result<Vec<Order>, RepositoryError> {
+        let mut journal = self.journal.lock().await;
+        let order_ids = self
+            .matching_ids(table_id, |order| order.session_id == Some(session_id))
+            .await?;
+        if order_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        append(
+            &mut journal,
+            &JournalEntry::Clear {
+                table_id,
+                order_ids,
+                archive: true,
+            },
+        )
+        .await?;
+        self.inner.archive_session(table_id, session_id).await
+    }
+
+    async fn list_archived(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+        self.inner.list_archived(table_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("mealdiner-journal-{}", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_replay_journal_after_restart() {
+        let dir = test_dir();
+        let orders = vec![Order::for_test(1), Order::for_test(1)];
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository.create(orders.clone()).await.unwrap();
+            assert!(repository.delete(1, orders[0].id).await.unwrap());
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        let restored = repository.list_by_table(1).await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].id, orders[1].id);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_then_journal_tail() {
+        let dir = test_dir();
+        let before = Order::for_test(2);
+        let after = Order::for_test(2);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository.create(vec![before.clone()]).await.unwrap();
+            repository.snapshot().await.unwrap();
+            assert_eq!(
+                std::fs::metadata(dir.join(JOURNAL_FILE)).unwrap().len(),
+                0,
+                "Snapshot should compact the journal"
+            );
+            repository.create(vec![after.clone()]).await.unwrap();
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        let ids: Vec<Uuid> = repository
+            .list_by_table(2)
+            .await
+            .unwrap()
+            .iter()
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(ids, vec![before.id, after.id]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_is_idempotent_over_snapshot() {
+        let dir = test_dir();
+        let order = Order::for_test(3);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository.create(vec![order.clone()]).await.unwrap();
+        }
+        // Simulate a crash after the snapshot was renamed but before the journal was truncated.
+        std::fs::write(
+            dir.join(SNAPSHOT_FILE),
+            serde_json::to_vec(&vec![order.clone()]).unwrap(),
+        )
+        .unwrap();
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert_eq!(repository.list_by_table(3).await.unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleared_table_survives_restart_and_snapshot() {
+        let dir = test_dir();
+        let served = Order {
+            status: OrderStatus::Served,
+            ..Order::for_test(5)
+        };
+        let pending = Order::for_test(5);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository
+                .create(vec![served.clone(), pending.clone()])
+                .await
+                .unwrap();
+            let removed = repository
+                .clear_table(5, &[OrderStatus::Served], true)
+                .await
+                .unwrap();
+            assert_eq!(removed.len(), 1);
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        let left = repository.list_by_table(5).await.unwrap();
+        assert_eq!((left.len(), left[0].id), (1, pending.id));
+        repository.snapshot().await.unwrap();
+        drop(repository);
+        // Replaying the clear again over the snapshot must not archive twice.
+        std::fs::write(
+            dir.join(JOURNAL_FILE),
+            format!(
+                "{}\n",
+                serde_json::to_string(&JournalEntry::Clear {
+                    table_id: 5,
+                    order_ids: vec![served.id],
+                    archive: true,
+                })
+                .unwrap()
+            ),
+        )
+        .unwrap();
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        let archived = repository.list_archived(5).await.unwrap();
+        assert_eq!((archived.len(), archived[0].id), (1, served.id));
+        assert_eq!(repository.list_by_table(5).await.unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archived_session_survives_restart() {
+        let dir = test_dir();
+        let session_id = Uuid::new_v4();
+        let seated = Order {
+            session_id: Some(session_id),
+            ..Order::for_test(6)
+        };
+        let walk_in = Order::for_test(6);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository
+                .create(vec![seated.clone(), walk_in.clone()])
+                .await
+                .unwrap();
+            let archived = repository.archive_session(6, session_id).await.unwrap();
+            assert_eq!(archived.len(), 1);
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        let left = repository.list_by_table(6).await.unwrap();
+        assert_eq!((left.len(), left[0].id), (1, walk_in.id));
+        assert_eq!(repository.list_archived(6).await.unwrap()[0].id, seated.id);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archived_tickets_keep_numbering_after_restart() {
+        let dir = test_dir();
+        let ticket = TicketRef {
+            id: Uuid::new_v4(),
+            number: 17,
+        };
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository
+                .create(vec![Order {
+                    ticket: Some(ticket),
+                    ..Order::for_test(3)
+                }])
+                .await
+                .unwrap();
+            repository
+                .clear_table(3, &OrderStatus::ALL, true)
+                .await
+                .unwrap();
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert_eq!(repository.latest_ticket().await.unwrap(), Some(ticket));
+        repository.snapshot().await.unwrap();
+        drop(repository);
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert_eq!(repository.latest_ticket().await.unwrap(), Some(ticket));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_torn_tail_is_ignored() {
+        let dir = test_dir();
+        let order = Order::for_test(4);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository.create(vec![order.clone()]).await.unwrap();
+        }
+        let mut journal = OpenOptions::new()
+            .append(true)
+            .open(dir.join(JOURNAL_FILE))
+            .unwrap();
+        std::io::Write::write_all(&mut journal, b"{\"op\":\"delete\",\"table_").unwrap();
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert_eq!(repository.list_by_table(4).await.unwrap().len(), 1);
+
+        // The torn bytes are cut off, so later appends stay readable.
+        let next = Order::for_test(4);
+        repository.create(vec![next]).await.unwrap();
+        drop(repository);
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert_eq!(repository.list_by_table(4).await.unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_update_is_replayed() {
+        let dir = test_dir();
+        let order = Order::for_test(5);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository.create(vec![order.clone()]).await.unwrap();
+            let outcome = repository
+                .update(
+                    5,
+                    order.id,
+                    Box::new(|order| order.transition_to(OrderStatus::Cooking)),
+                )
+                .await
+                .unwrap();
+            assert!(matches!(outcome, UpdateOutcome::Updated(_)));
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        let restored = repository.get(5, order.id).await.unwrap().unwrap();
+        assert_eq!(restored.status, OrderStatus::Cooking);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_id_is_replayed() {
+        let dir = test_dir();
+        let order = Order::for_test(6);
+        {
+            let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+            repository.create(vec![order.clone()]).await.unwrap();
+            assert!(repository.delete_by_id(order.id).await.unwrap().is_some());
+        }
+
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert!(repository.get_by_id(order.id).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_missing_order_is_not_journaled() {
+        let dir = test_dir();
+        let repository = JournaledOrderRepository::open(&dir).await.unwrap();
+        assert!(!repository.delete(1, Uuid::new_v4()).await.unwrap());
+        assert_eq!(std::fs::metadata(dir.join(JOURNAL_FILE)).unwrap().len(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,508 @@
+use crate::order::model::{Order, OrderStatus};
+use crate::order::repository::{OrderRepository, OrderUpdate, RepositoryError, UpdateOutcome};
+use crate::order::ticket::TicketRef;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Tables are spread over this many independently locked shards. A write only blocks
+/// readers of tables that hash to the same shard, not the whole store.
+const SHARD_COUNT: usize = 64;
+
+/// One table's orders. `positions` gives O(1) lookup by id while `orders` keeps the
+/// insertion order for listing.
+#[derive(Default)]
+struct TableOrders {
+    orders: BTreeMap<u64, Order>,
+    positions: HashMap<Uuid, u64>,
+    next_position: u64,
+}
+
+impl TableOrders {
+    fn push(&mut self, order: Order) {
+        let position = self.next_position;
+        self.next_position += 1;
+        self.positions.insert(order.id, position);
+        self.orders.insert(position, order);
+    }
+
+    fn get(&self, order_id: Uuid) -> Option<&Order> {
+        self.positions
+            .get(&order_id)
+            .and_then(|position| self.orders.get(position))
+    }
+
+    fn get_mut(&mut self, order_id: Uuid) -> Option<&mut Order> {
+        self.positions
+            .get(&order_id)
+            .and_then(|position| self.orders.get_mut(position))
+    }
+
+    fn remove(&mut self, order_id: Uuid) -> Option<Order> {
+        let position = self.positions.remove(&order_id)?;
+        self.orders.remove(&position)
+    }
+}
+
+type Shard = RwLock<HashMap<u32, TableOrders>>;
+type IndexShard = RwLock<HashMap<Uuid, u32>>;
+
+/// Default backend: everything lives in process-local maps keyed by table id, plus a
+/// sharded `order_id -> table_id` index for lookups that don't know the table.
+pub struct InMemoryOrderRepository {
+    shards: Box<[Shard]>,
+    index: Box<[IndexShard]>,
+    archive: RwLock<HashMap<u32, Vec<Order>>>,
+}
+
+impl Default for InMemoryOrderRepository {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| Shard::default()).collect(),
+            index: (0..SHARD_COUNT).map(|_| IndexShard::default()).collect(),
+            archive: RwLock::default(),
+        }
+    }
+}
+
+impl InMemoryOrderRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn shard(&self, table_id: u32) -> &Shard {
+        &self.shards[table_id as usize % self.shards.len()]
+    }
+
+    fn index_shard(&self, order_id: Uuid) -> &IndexShard {
+        &self.index[(order_id.as_u128() % self.index.len() as u128) as usize]
+    }
+
+    async fn table_of(&self, order_id: Uuid) -> Option<u32> {
+        self.index_shard(order_id)
+            .read()
+            .await
+            .get(&order_id)
+            .copied()
+    }
+
+    async fn remove(&self, table_id: u32, order_id: Uuid) -> Option<Order> {
+        let removed = {
+            let mut store = self.shard(table_id).write().await;
+            let table = store.get_mut(&table_id)?;
+            let removed = table.remove(order_id);
+            if table.orders.is_empty() {
+                store.remove(&table_id);
+            }
+            removed
+        };
+        if removed.is_some() {
+            self.index_shard(order_id).write().await.remove(&order_id);
+        }
+        removed
+    }
+
+    /// Take the table's orders that match off it, archiving them when asked.
+    async fn take(
+        &self,
+        table_id: u32,
+        matches: impl Fn(&Order) -> bool,
+        archive: bool,
+    ) -> Vec<Order> {
+        let removed = {
+            let mut store = self.shard(table_id).write().await;
+            let Some(table) = store.get_mut(&table_id) else {
+                return Vec::new();
+            };
+            let ids: Vec<Uuid> = table
+                .orders
+                .values()
+                .filter(|order| matches(order))
+                .map(|order| order.id)
+                .collect();
+            let removed: Vec<Order> = ids.iter().filter_map(|id| table.remove(*id)).collect();
+            if table.orders.is_empty() {
+                store.remove(&table_id);
+            }
+            // Archive before the shard lock is released, so no reader sees an order
+            // in neither place.
+            if archive && !removed.is_empty() {
+                self.archive
+                    .write()
+                    .await
+                    .entry(table_id)
+                    .or_default()
+                    .extend(removed.iter().cloned());
+            }
+            removed
+        };
+        for order in &removed {
+            self.index_shard(order.id).write().await.remove(&order.id);
+        }
+        removed
+    }
+
+    /// Every stored order, each table's orders kept in insertion order.
+    pub async fn all_orders(&self) -> Vec<Order> {
+        let mut orders = Vec::new();
+        for shard in self.shards.iter() {
+            let store = shard.read().await;
+            orders.extend(
+                store
+                    .values()
+                    .flat_map(|table| table.orders.values().cloned()),
+            );
+        }
+        orders
+    }
+
+    /// Every archived order, each table's kept in archive order.
+    pub async fn all_archived(&self) -> Vec<Order> {
+        self.archive
+            .read()
+            .await
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Put previously archived orders back into the archive, e.g. from a snapshot.
+    pub async fn restore_archived(&self, orders: Vec<Order>) {
+        let mut archive = self.archive.write().await;
+        for order in orders {
+            archive.entry(order.table_id).or_default().push(order);
+        }
+    }
+}
+
+#[async_trait]
+impl OrderRepository for InMemoryOrderRepository {
+    async fn create(&self, orders: Vec<Order>) -> Result<(), RepositoryError> {
+        // Group first so readers never observe half of a table's batch.
+        let mut by_table: HashMap<u32, Vec<Order>> = HashMap::new();
+        for order in orders {
+            by_table.entry(order.table_id).or_default().push(order);
+        }
+        for (table_id, table_orders) in by_table {
+            let ids: Vec<Uuid> = table_orders.iter().map(|order| order.id).collect();
+            {
+                let mut store = self.shard(table_id).write().await;
+                let table = store.entry(table_id).or_default();
+                for order in table_orders {
+                    table.push(order);
+                }
+            }
+            for order_id in ids {
+                self.index_shard(order_id)
+                    .write()
+                    .await
+                    .insert(order_id, table_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn list_by_table(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+        let store = self.shard(table_id).read().await;
+        Ok(store
+            .get(&table_id)
+            .map(|table| table.orders.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn list_by_status(
+        &self,
+        statuses: &[OrderStatus],
+    ) -> Result<Vec<Order>, RepositoryError> {
+        let mut orders = Vec::new();
+        for shard in self.shards.iter() {
+            let store = shard.read().await;
+            orders.extend(
+                store
+                    .values()
+                    .flat_map(|table| table.orders.values())
+                    .filter(|order| statuses.contains(&order.status))
+                    .cloned(),
+            );
+        }
+        Ok(orders)
+    }
+
+    async fn list_by_ticket(&self, ticket_id: Uuid) -> Result<Vec<Order>, RepositoryError> {
+        // A ticket never spans tables, so the first table holding it has all of it.
+        for shard in self.shards.iter() {
+            let store = shard.read().await;
+            let found: Vec<Order> = store
+                .values()
+                .flat_map(|table| table.orders.values())
+                .filter(|order| order.ticket.is_some_and(|ticket| ticket.id == ticket_id))
+                .cloned()
+                .collect();
+            if !found.is_empty() {
+                return Ok(found);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    async fn latest_ticket(&self) -> Result<Option<TicketRef>, RepositoryError> {
+        let mut latest = None;
+        for shard in self.shards.iter() {
+            let store = shard.read().await;
+            latest = store
+                .values()
+                .flat_map(|table| table.orders.values())
+                .filter_map(|order| Some((order.created_at, order.ticket?)))
+                .chain(latest)
+                .max_by_key(|(created_at, _)| *created_at);
+        }
+        let archive = self.archive.read().await;
+        let latest = archive
+            .values()
+            .flatten()
+            .filter_map(|order| Some((order.created_at, order.ticket?)))
+            .chain(latest)
+            .max_by_key(|(created_at, _)| *created_at);
+        Ok(latest.map(|(_, ticket)| ticket))
+    }
+
+    async fn get(&self, table_id: u32, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        let store = self.shard(table_id).read().await;
+        Ok(store
+            .get(&table_id)
+            .and_then(|table| table.get(order_id))
+            .cloned())
+    }
+
+    async fn get_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        match self.table_of(order_id).await {
+            Some(table_id) => self.get(table_id, order_id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn update(
+        &self,
+        table_id: u32,
+        order_id: Uuid,
+        apply: OrderUpdate,
+    ) -> Result<UpdateOutcome, RepositoryError> {
+        let mut store = self.shard(table_id).write().await;
+        let Some(order) = store
+            .get_mut(&table_id)
+            .and_then(|table| table.get_mut(order_id))
+        else {
+            return Ok(UpdateOutcome::NotFound);
+        };
+        // Work on a copy so a refused change leaves the stored order untouched.
+        let mut updated = order.clone();
+        if let Err(reason) = apply(&mut updated) {
+            return Ok(UpdateOutcome::Rejected(reason));
+        }
+        *order = updated.clone();
+        Ok(UpdateOutcome::Updated(Box::new(updated)))
+    }
+
+    async fn delete(&self, table_id: u32, order_id: Uuid) -> Result<bool, RepositoryError> {
+        Ok(self.remove(table_id, order_id).await.is_some())
+    }
+
+    async fn delete_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        match self.table_of(order_id).await {
+            Some(table_id) => Ok(self.remove(table_id, order_id).await),
+            None => Ok(None),
+        }
+    }
+
+    async fn clear_table(
+        &self,
+        table_id: u32,
+        statuses: &[OrderStatus],
+        archive: bool,
+    ) -> Result<Vec<Order>, RepositoryError> {
+        Ok(self
+            .take(table_id, |order| statuses.contains(&order.status), archive)
+            .await)
+    }
+
+    async fn archive_session(
+        &self,
+        table_id: u32,
+        session_id: Uuid,
+    ) -> Result<Vec<Order>, RepositoryError> {
+        Ok(self
+            .take(table_id, |order| order.session_id == Some(session_id), true)
+            .await)
+    }
+
+    async fn list_archived(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+        Ok(self
+            .archive
+            .read()
+            .await
+            .get(&table_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::model::OrderUpdateError;
+
+    #[tokio::test]
+    async fn test_create_and_list_by_table() {
+        let repository = InMemoryOrderRepository::new();
+        let orders = vec![Order::for_test(1), Order::for_test(1), Order::for_test(2)];
+        repository.create(orders.clone()).await.unwrap();
+
+        let table_one = repository.list_by_table(1).await.unwrap();
+        assert_eq!(table_one.len(), 2);
+        assert_eq!(table_one[0].id, orders[0].id);
+        assert_eq!(table_one[1].id, orders[1].id);
+        assert_eq!(repository.list_by_table(2).await.unwrap().len(), 1);
+        assert!(repository.list_by_table(3).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_is_scoped_to_table() {
+        let repository = InMemoryOrderRepository::new();
+        let order = Order::for_test(1);
+        repository.create(vec![order.clone()]).await.unwrap();
+
+        assert!(repository.get(1, order.id).await.unwrap().is_some());
+        assert!(repository.get(2, order.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_write_lock_only_blocks_own_shard() {
+        let repository = InMemoryOrderRepository::new();
+        repository.create(vec![Order::for_test(2)]).await.unwrap();
+
+        let _guard = repository.shard(1).write().await;
+        let other_table = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            repository.list_by_table(2),
+        )
+        .await;
+        assert_eq!(other_table.unwrap().unwrap().len(), 1);
+
+        let same_shard = tokio::time::timeout(
+            std::time::Duration::from_millis(10),
+            repository.list_by_table(1 + SHARD_COUNT as u32),
+        )
+        .await;
+        assert!(same_shard.is_err(), "Tables on a locked shard should wait");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_and_delete_by_id() {
+        let repository = InMemoryOrderRepository::new();
+        let order = Order::for_test(9);
+        let other = Order::for_test(9);
+        repository
+            .create(vec![order.clone(), other.clone()])
+            .await
+            .unwrap();
+
+        let found = repository.get_by_id(order.id).await.unwrap().unwrap();
+        assert_eq!(found.table_id, 9);
+        assert!(repository
+            .get_by_id(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+
+        let removed = repository.delete_by_id(order.id).await.unwrap().unwrap();
+        assert_eq!(removed.id, order.id);
+        assert!(repository.get_by_id(order.id).await.unwrap().is_none());
+        assert!(repository.delete_by_id(order.id).await.unwrap().is_none());
+
+        let remaining = repository.list_by_table(9).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, other.id);
+    }
+
+    #[tokio::test]
+    async fn test_update_applies_or_rejects() {
+        let repository = InMemoryOrderRepository::new();
+        let order = Order::for_test(1);
+        repository.create(vec![order.clone()]).await.unwrap();
+
+        let outcome = repository
+            .update(
+                1,
+                order.id,
+                Box::new(|order| order.transition_to(OrderStatus::Cooking)),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Updated(o) if o.status == OrderStatus::Cooking));
+
+        let outcome = repository
+            .update(
+                1,
+                order.id,
+                Box::new(|order| {
+                    order.status = OrderStatus::Served;
+                    Err(OrderUpdateError::Finished(OrderStatus::Served))
+                }),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Rejected(_)));
+        let stored = repository.get(1, order.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, OrderStatus::Cooking);
+
+        let outcome = repository
+            .update(2, order.id, Box::new(|_| Ok(())))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, UpdateOutcome::NotFound));
+    }
+
+    #[tokio::test]
+    async fn test_list_by_status_spans_tables() {
+        let repository = InMemoryOrderRepository::new();
+        let cooking = Order::for_test(1);
+        repository
+            .create(vec![cooking.clone(), Order::for_test(2)])
+            .await
+            .unwrap();
+        repository
+            .update(
+                1,
+                cooking.id,
+                Box::new(|order| order.transition_to(OrderStatus::Cooking)),
+            )
+            .await
+            .unwrap();
+
+        let unfinished = repository
+            .list_by_status(&[OrderStatus::Pending, OrderStatus::Cooking])
+            .await
+            .unwrap();
+        assert_eq!(unfinished.len(), 2);
+        let cooking_only = repository
+            .list_by_status(&[OrderStatus::Cooking])
+            .await
+            .unwrap();
+        assert_eq!(cooking_only.len(), 1);
+        assert_eq!(cooking_only[0].id, cooking.id);
+    }
+
+    #[tokio::test]
+    async fn test_delete() {
+        let repository = InMemoryOrderRepository::new();
+        let order = Order::for_test(1);
+        repository.create(vec![order.clone()]).await.unwrap();
+
+        assert!(!repository.delete(2, order.id).await.unwrap());
+        assert!(repository.delete(1, order.id).await.unwrap());
+        assert!(!repository.delete(1, order.id).await.unwrap());
+        assert!(repository.list_by_table(1).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+pub mod journal;
+pub mod memory;
+pub mod sqlite;
+
+use crate::order::model::{Order, OrderStatus, OrderUpdateError};
+use crate::order::ticket::TicketRef;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+pub use journal::JournaledOrderRepository;
+pub use memory::InMemoryOrderRepository;
+pub use sqlite::SqliteOrderRepository;
+
+pub use crate::storage::RepositoryError;
+
+/// A change to one stored order, run while the backend holds that order exclusively.
+/// Returning an error refuses the change and nothing is written.
+pub type OrderUpdate = Box<dyn FnOnce(&mut Order) -> Result<(), OrderUpdateError> + Send>;
+
+#[derive(Debug)]
+pub enum UpdateOutcome {
+    Updated(Box<Order>),
+    Rejected(OrderUpdateError),
+    NotFound,
+}
+
+/// Storage boundary for orders. Handlers only talk to this trait so the backend
+/// can be swapped (or faked in tests) without touching the route code.
+#[async_trait]
+pub trait OrderRepository: Send + Sync {
+    /// Persist a batch of new orders. Every order keeps its own `table_id`.
+    async fn create(&self, orders: Vec<Order>) -> Result<(), RepositoryError>;
+
+    /// All orders of a table, in insertion order. Unknown tables yield an empty list.
+    async fn list_by_table(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError>;
+
+    /// Orders across every table whose status is one of `statuses`.
+    async fn list_by_status(&self, statuses: &[OrderStatus])
+        -> Result<Vec<Order>, RepositoryError>;
+
+    /// Orders placed on one ticket, in insertion order.
+    async fn list_by_ticket(&self, ticket_id: Uuid) -> Result<Vec<Order>, RepositoryError>;
+
+    /// Ticket of the most recently placed order that has one, archived orders
+    /// included, so numbering can resume after a restart.
+    async fn latest_ticket(&self) -> Result<Option<TicketRef>, RepositoryError>;
+
+    async fn get(&self, table_id: u32, order_id: Uuid) -> Result<Option<Order>, RepositoryError>;
+
+    /// Look an order up by its id alone, without knowing its table.
+    async fn get_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError>;
+
+    /// Atomically read-modify-write one order of a table.
+    async fn update(
+        &self,
+        table_id: u32,
+        order_id: Uuid,
+        apply: OrderUpdate,
+    ) -> Result<UpdateOutcome, RepositoryError>;
+
+    /// Returns `true` when an order was actually removed.
+    async fn delete(&self, table_id: u32, order_id: Uuid) -> Result<bool, RepositoryError>;
+
+    /// Remove an order by its id alone. Returns the removed order, if any.
+    async fn delete_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError>;
+
+    /// Atomically take every order of a table whose status is one of `statuses` off
+    /// the table, returning them in insertion order. With `archive` they move to the
+    /// table's archive instead of being dropped.
+    async fn clear_table(
+        &self,
+        table_id: u32,
+        statuses: &[OrderStatus],
+        archive: bool,
+    ) -> Result<Vec<Order>, RepositoryError>;
+
+    /// Atomically move every order of `session_id` at the table to its archive,
+    /// whatever its status, returning them in insertion order.
+    async fn archive_session(
+        &self,
+        table_id: u32,
+        session_id: Uuid,
+    ) -> Result<Vec<Order>, RepositoryError>;
+
+    /// Archived orders of a table, oldest first.
+    async fn list_archived(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError>;
+}
@@ -0,0 +1,528 @@
+use crate::order::model::{Order, OrderStatus};
+use crate::order::repository::{OrderRepository, OrderUpdate, UpdateOutcome};
+use crate::order::ticket::TicketRef;
+use crate::storage::{RepositoryError, SqliteDatabase};
+use async_trait::async_trait;
+use rusqlite::{params, params_from_iter, Connection};
+use uuid::Uuid;
+
+/// Durable backend. Each order is stored as a JSON document next to the columns we
+/// query by, so adding fields to `Order` does not need a migration on its own.
+pub struct SqliteOrderRepository {
+    db: SqliteDatabase,
+}
+
+impl SqliteOrderRepository {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    #[cfg(test)]
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, RepositoryError> {
+        Ok(Self::new(SqliteDatabase::open(path)?))
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, RepositoryError> {
+        Ok(Self::new(SqliteDatabase::open_in_memory()?))
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, RepositoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, RepositoryError> + Send + 'static,
+    {
+        self.db.run(f).await
+    }
+}
+
+fn decode(payload: String) -> Result<Order, RepositoryError> {
+    Ok(serde_json::from_str(&payload)?)
+}
+
+/// Move the orders `filter` selects off their table in one transaction, into the
+/// archive when asked. `filter` is a `FROM orders WHERE ...` clause bound to `params`.
+fn take(
+    conn: &mut Connection,
+    filter: &str,
+    params: &[String],
+    archive: bool,
+) -> Result<Vec<Order>, RepositoryError> {
+    let tx = conn.transaction()?;
+    let payloads = {
+        let mut stmt = tx.prepare(&format!("SELECT payload {} ORDER BY rowid", filter))?;
+        let rows = stmt.query_map(params_from_iter(params.iter()), |row| {
+            row.get::<_, String>(0)
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+    if archive {
+        tx.execute(
+            &format!(
+                "INSERT INTO archived_orders (id, table_id, payload)
+                SELECT id, table_id, payload {} ORDER BY rowid",
+                filter
+            ),
+            params_from_iter(params.iter()),
+        )?;
+    }
+    tx.execute(
+        &format!("DELETE {}", filter),
+        params_from_iter(params.iter()),
+    )?;
+    tx.commit()?;
+    payloads.into_iter().map(decode).collect()
+}
+
+#[async_trait]
+impl OrderRepository for SqliteOrderRepository {
+    async fn create(&self, orders: Vec<Order>) -> Result<(), RepositoryError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT INTO orders (id, table_id, created_at, status, ticket_id, payload)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                for order in &orders {
+                    stmt.execute(params![
+                        order.id.to_string(),
+                        order.table_id,
+                        order.created_at.to_rfc3339(),
+                        order.status.to_string(),
+                        order.ticket.map(|ticket| ticket.id.to_string()),
+                        serde_json::to_string(order)?,
+                    ])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_by_table(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let mut stmt = conn
+                .prepare_cached("SELECT payload FROM orders WHERE table_id = ?1 ORDER BY rowid")?;
+            let payloads = stmt
+                .query_map(params![table_id], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            payloads.into_iter().map(decode).collect()
+        })
+        .await
+    }
+
+    async fn list_by_status(
+        &self,
+        statuses: &[OrderStatus],
+    ) -> Result<Vec<Order>, RepositoryError> {
+        let statuses: Vec<String> = statuses.iter().map(ToString::to_string).collect();
+        self.run(move |conn| {
+            let placeholders = vec!["?"; statuses.len()].join(", ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT payload FROM orders WHERE status IN ({}) ORDER BY rowid",
+                placeholders
+            ))?;
+            let payloads = stmt
+                .query_map(params_from_iter(statuses.iter()), |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            payloads.into_iter().map(decode).collect()
+        })
+        .await
+    }
+
+    async fn list_by_ticket(&self, ticket_id: Uuid) -> Result<Vec<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let mut stmt = conn
+                .prepare_cached("SELECT payload FROM orders WHERE ticket_id = ?1 ORDER BY rowid")?;
+            let payloads = stmt
+                .query_map(params![ticket_id.to_string()], |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            payloads.into_iter().map(decode).collect()
+        })
+        .await
+    }
+
+    async fn latest_ticket(&self) -> Result<Option<TicketRef>, RepositoryError> {
+        self.run(move |conn| {
+            // Archived orders keep no ticket column and leave in any order, so they
+            // are ranked by when they were placed.
+            let queries = [
+                "SELECT payload FROM orders WHERE ticket_id IS NOT NULL
+                ORDER BY rowid DESC LIMIT 1",
+                "SELECT payload FROM archived_orders
+                WHERE json_extract(payload, '$.ticket') IS NOT NULL
+                ORDER BY julianday(json_extract(payload, '$.created_at')) DESC LIMIT 1",
+            ];
+            let mut latest: Option<Order> = None;
+            for query in queries {
+                let mut stmt = conn.prepare_cached(query)?;
+                let mut rows = stmt.query([])?;
+                if let Some(row) = rows.next()? {
+                    let order = decode(row.get(0)?)?;
+                    if latest
+                        .as_ref()
+                        .is_none_or(|l| l.created_at < order.created_at)
+                    {
+                        latest = Some(order);
+                    }
+                }
+            }
+            Ok(latest.and_then(|order| order.ticket))
+        })
+        .await
+    }
+
+    async fn get(&self, table_id: u32, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let mut stmt =
+                conn.prepare_cached("SELECT payload FROM orders WHERE table_id = ?1 AND id = ?2")?;
+            let mut rows = stmt.query(params![table_id, order_id.to_string()])?;
+            match rows.next()? {
+                Some(row) => decode(row.get(0)?).map(Some),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    async fn get_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let mut stmt = conn.prepare_cached("SELECT payload FROM orders WHERE id = ?1")?;
+            let mut rows = stmt.query(params![order_id.to_string()])?;
+            match rows.next()? {
+                Some(row) => decode(row.get(0)?).map(Some),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    async fn update(
+        &self,
+        table_id: u32,
+        order_id: Uuid,
+        apply: OrderUpdate,
+    ) -> Result<UpdateOutcome, RepositoryError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            let payload: Option<String> = {
+                let mut stmt = tx
+                    .prepare_cached("SELECT payload FROM orders WHERE table_id = ?1 AND id = ?2")?;
+                let mut rows = stmt.query(params![table_id, order_id.to_string()])?;
+                match rows.next()? {
+                    Some(row) => Some(row.get(0)?),
+                    None => None,
+                }
+            };
+            let Some(payload) = payload else {
+                return Ok(UpdateOutcome::NotFound);
+            };
+            let mut order = decode(payload)?;
+            if let Err(reason) = apply(&mut order) {
+                return Ok(UpdateOutcome::Rejected(reason));
+            }
+            tx.execute(
+                "UPDATE orders SET status = ?1, payload = ?2 WHERE id = ?3",
+                params![
+                    order.status.to_string(),
+                    serde_json::to_string(&order)?,
+                    order_id.to_string()
+                ],
+            )?;
+            tx.commit()?;
+            Ok(UpdateOutcome::Updated(Box::new(order)))
+        })
+        .await
+    }
+
+    async fn delete(&self, table_id: u32, order_id: Uuid) -> Result<bool, RepositoryError> {
+        self.run(move |conn| {
+            let removed = conn.execute(
+                "DELETE FROM orders WHERE table_id = ?1 AND id = ?2",
+                params![table_id, order_id.to_string()],
+            )?;
+            Ok(removed > 0)
+        })
+        .await
+    }
+
+    async fn delete_by_id(&self, order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let mut stmt =
+                conn.prepare_cached("DELETE FROM orders WHERE id = ?1 RETURNING payload")?;
+            let mut rows = stmt.query(params![order_id.to_string()])?;
+            match rows.next()? {
+                Some(row) => decode(row.get(0)?).map(Some),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    async fn clear_table(
+        &self,
+        table_id: u32,
+        statuses: &[OrderStatus],
+        archive: bool,
+    ) -> Result<Vec<Order>, RepositoryError> {
+        let statuses: Vec<String> = statuses.iter().map(ToString::to_string).collect();
+        self.run(move |conn| {
+            let placeholders = vec!["?"; statuses.len()].join(", ");
+            let filter = format!(
+                "FROM orders WHERE table_id = {} AND status IN ({})",
+                table_id, placeholders
+            );
+            take(conn, &filter, &statuses, archive)
+        })
+        .await
+    }
+
+    async fn archive_session(
+        &self,
+        table_id: u32,
+        session_id: Uuid,
+    ) -> Result<Vec<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let filter = format!(
+                "FROM orders WHERE table_id = {} AND json_extract(payload, '$.session_id') = ?",
+                table_id
+            );
+            take(conn, &filter, &[session_id.to_string()], true)
+        })
+        .await
+    }
+
+    async fn list_archived(&self, table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+        self.run(move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT payload FROM archived_orders WHERE table_id = ?1 ORDER BY rowid",
+            )?;
+            let payloads = stmt
+                .query_map(params![table_id], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            payloads.into_iter().map(decode).collect()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_create_list_get_delete() {
+        let repository = SqliteOrderRepository::open_in_memory().unwrap();
+        let orders = vec![Order::for_test(1), Order::for_test(1), Order::for_test(2)];
+        repository.create(orders.clone()).await.unwrap();
+
+        let table_one = repository.list_by_table(1).await.unwrap();
+        assert_eq!(table_one.len(), 2);
+        assert_eq!(table_one[0].id, orders[0].id);
+        assert_eq!(table_one[1].id, orders[1].id);
+
+        let found = repository.get(1, orders[0].id).await.unwrap().unwrap();
+        assert_eq!(found.menu.name, "Test Menu");
+        assert!(repository.get(2, orders[0].id).await.unwrap().is_none());
+
+        assert!(repository.delete(1, orders[0].id).await.unwrap());
+        assert!(!repository.delete(1, orders[0].id).await.unwrap());
+        assert_eq!(repository.list_by_table(1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_and_delete_by_id() {
+        let repository = SqliteOrderRepository::open_in_memory().unwrap();
+        let order = Order::for_test(5);
+        repository.create(vec![order.clone()]).await.unwrap();
+
+        let found = repository.get_by_id(order.id).await.unwrap().unwrap();
+        assert_eq!(found.table_id, 5);
+
+        let removed = repository.delete_by_id(order.id).await.unwrap().unwrap();
+        assert_eq!(removed.id, order.id);
+        assert!(repository.get_by_id(order.id).await.unwrap().is_none());
+        assert!(repository.delete_by_id(order.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_by_ticket_and_latest_ticket() {
+        let repository = SqliteOrderRepository::open_in_memory().unwrap();
+        assert!(repository.latest_ticket().await.unwrap().is_none());
+        let ticket = TicketRef {
+            id: Uuid::new_v4(),
+            number: 41,
+        };
+        let orders = vec![
+            Order {
+                ticket: Some(ticket),
+                ..Order::for_test(2)
+            },
+            Order::for_test(2),
+            Order {
+                ticket: Some(ticket),
+                ..Order::for_test(2)
+            },
+        ];
+        repository.create(orders.clone()).await.unwrap();
+
+        let on_ticket = repository.list_by_ticket(ticket.id).await.unwrap();
+        let ids: Vec<Uuid> = on_ticket.iter().map(|order| order.id).collect();
+        assert_eq!(ids, [orders[0].id, orders[2].id]);
+        assert_eq!(repository.latest_ticket().await.unwrap(), Some(ticket));
+
+        // Archiving the table must not send numbering back.
+        repository
+            .clear_table(2, &OrderStatus::ALL, true)
+            .await
+            .unwrap();
+        assert_eq!(repository.latest_ticket().await.unwrap(), Some(ticket));
+        // A live order placed earlier does not outrank the archived one.
+        let backdated = TicketRef {
+            id: Uuid::new_v4(),
+            number: 42,
+        };
+        repository
+            .create(vec![Order {
+                ticket: Some(backdated),
+                created_at: orders[0].created_at - chrono::Duration::minutes(1),
+                ..Order::for_test(3)
+            }])
+            .await
+            .unwrap();
+        assert_eq!(repository.latest_ticket().await.unwrap(), Some(ticket));
+    }
+
+    #[tokio::test]
+    async fn test_clear_table_deletes_or_archives() {
+        let repository = SqliteOrderRepository::open_in_memory().unwrap();
+        let served = Order {
+            status: OrderStatus::Served,
+            ..Order::for_test(8)
+        };
+        let pending = Order::for_test(8);
+        let other_table = Order::for_test(9);
+        repository
+            .create(vec![served.clone(), pending.clone(), other_table.clone()])
+            .await
+            .unwrap();
+
+        let removed = repository
+            .clear_table(8, &[OrderStatus::Served], true)
+            .await
+            .unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(repository.list_archived(8).await.unwrap()[0].id, served.id);
+
+        let removed = repository
+            .clear_table(8, &OrderStatus::ALL, false)
+            .await
+            .unwrap();
+        assert_eq!(removed[0].id, pending.id);
+        assert!(repository.list_by_table(8).await.unwrap().is_empty());
+        assert_eq!(repository.list_archived(8).await.unwrap().len(), 1);
+        assert_eq!(repository.list_by_table(9).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_archive_session_leaves_other_sessions() {
+        let repository = SqliteOrderRepository::open_in_memory().unwrap();
+        let (leaving, next) = (Uuid::new_v4(), Uuid::new_v4());
+        let served = Order {
+            status: OrderStatus::Served,
+            session_id: Some(leaving),
+            ..Order::for_test(8)
+        };
+        let pending = Order {
+            session_id: Some(leaving),
+            ..Order::for_test(8)
+        };
+        let next_party = Order {
+            session_id: Some(next),
+            ..Order::for_test(8)
+        };
+        repository
+            .create(vec![served.clone(), next_party.clone(), pending.clone()])
+            .await
+            .unwrap();
+
+        let archived = repository.archive_session(8, leaving).await.unwrap();
+        let ids: Vec<Uuid> = archived.iter().map(|order| order.id).collect();
+        assert_eq!(ids, [served.id, pending.id]);
+        let left = repository.list_by_table(8).await.unwrap();
+        assert_eq!((left.len(), left[0].id), (1, next_party.id));
+        assert_eq!(repository.list_archived(8).await.unwrap().len(), 2);
+        assert!(repository
+            .archive_session(8, leaving)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_persists_status() {
+        let repository = SqliteOrderRepository::open_in_memory().unwrap();
+        let order = Order::for_test(3);
+        repository.create(vec![order.clone()]).await.unwrap();
+
+        let outcome = repository
+            .update(
+                3,
+                order.id,
+                Box::new(|order| order.transition_to(OrderStatus::Cooking)),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Updated(_)));
+
+        let outcome = repository
+            .update(
+                3,
+                order.id,
+                Box::new(|order| order.transition_to(OrderStatus::Served)),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Rejected(_)));
+
+        let stored = repository.get(3, order.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, OrderStatus::Cooking);
+
+        let cooking = repository
+            .list_by_status(&[OrderStatus::Cooking])
+            .await
+            .unwrap();
+        assert_eq!(cooking.len(), 1);
+        assert!(repository
+            .list_by_status(&[OrderStatus::Pending])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_orders_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("mealdiner-{}.db", Uuid::new_v4()));
+        let order = Order::for_test(7);
+        {
+            let repository = SqliteOrderRepository::open(&path).unwrap();
+            repository.create(vec![order.clone()]).await.unwrap();
+        }
+
+        let repository = SqliteOrderRepository::open(&path).unwrap();
+        let orders = repository.list_by_table(7).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].id, order.id);
+
+        drop(repository);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
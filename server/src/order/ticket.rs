@@ -0,0 +1,100 @@
+use crate::locale::PreferredLocales;
+use crate::order::model::{Order, OrderResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use uuid::Uuid;
+
+/// Ticket numbers count up to this and then start again at 1, so the number
+/// called out at the pass stays short.
+pub const MAX_TICKET_NUMBER: u32 = 9999;
+
+/// The create request an order came from. Every order of one `POST /orders`
+/// shares it, so the kitchen can see what was ordered together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketRef {
+    pub id: Uuid,
+    /// Human-readable number, unique among the branch's recent tickets.
+    pub number: u32,
+}
+
+/// Hands out this branch's ticket numbers. One server runs one branch, so an
+/// in-process counter is enough; it resumes after the last stored ticket.
+#[derive(Debug, Default)]
+pub struct TicketCounter {
+    last: AtomicU32,
+}
+
+impl TicketCounter {
+    pub fn starting_after(last: u32) -> Self {
+        Self {
+            last: AtomicU32::new(last % MAX_TICKET_NUMBER),
+        }
+    }
+
+    pub fn next(&self) -> TicketRef {
+        let previous = self
+            .last
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(last % MAX_TICKET_NUMBER + 1)
+            })
+            .unwrap_or_default();
+        TicketRef {
+            id: Uuid::new_v4(),
+            number: previous % MAX_TICKET_NUMBER + 1,
+        }
+    }
+}
+
+/// A ticket with the orders placed on it.
+#[derive(Debug, Serialize)]
+pub struct TicketResponse {
+    pub id: Uuid,
+    pub number: u32,
+    pub table_id: u32,
+    pub created_at: DateTime<Utc>,
+    pub items: Vec<OrderResponse>,
+}
+
+impl TicketResponse {
+    /// Group `orders` by ticket, oldest ticket first. Orders placed before
+    /// tickets existed have none and are left out.
+    pub fn group(
+        orders: Vec<Order>,
+        now: DateTime<Utc>,
+        locales: &PreferredLocales,
+    ) -> Vec<TicketResponse> {
+        let mut tickets: Vec<TicketResponse> = Vec::new();
+        for order in orders {
+            let Some(ticket) = order.ticket else {
+                continue;
+            };
+            let item = OrderResponse::at(order, now).localized(locales);
+            match tickets.iter_mut().find(|t| t.id == ticket.id) {
+                Some(existing) => existing.items.push(item),
+                None => tickets.push(TicketResponse {
+                    id: ticket.id,
+                    number: ticket.number,
+                    table_id: item.order.table_id,
+                    created_at: item.order.created_at,
+                    items: vec![item],
+                }),
+            }
+        }
+        tickets.sort_by_key(|ticket| ticket.created_at);
+        tickets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticket_numbers_wrap_after_max() {
+        let counter = TicketCounter::starting_after(MAX_TICKET_NUMBER - 1);
+        let numbers: Vec<u32> = (0..3).map(|_| counter.next().number).collect();
+        assert_eq!(numbers, [MAX_TICKET_NUMBER, 1, 2]);
+        assert_eq!(TicketCounter::default().next().number, 1);
+    }
+}
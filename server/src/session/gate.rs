@@ -0,0 +1,49 @@
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Tables are spread over this many independently locked shards, like the order store's.
+const SHARD_COUNT: usize = 64;
+
+/// Keeps orders out of a session while it closes. Orders hold their table's shard
+/// shared from the session check to the insert, a close holds it exclusively from the
+/// session check to the close, so other tables never wait on either.
+pub struct SeatingGate {
+    shards: Box<[RwLock<()>]>,
+}
+
+impl Default for SeatingGate {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| RwLock::default()).collect(),
+        }
+    }
+}
+
+impl SeatingGate {
+    fn shard(&self, table_id: u32) -> &RwLock<()> {
+        &self.shards[table_id as usize % self.shards.len()]
+    }
+
+    pub async fn ordering(&self, table_id: u32) -> RwLockReadGuard<'_, ()> {
+        self.shard(table_id).read().await
+    }
+
+    pub async fn closing(&self, table_id: u32) -> RwLockWriteGuard<'_, ()> {
+        self.shard(table_id).write().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_closing_only_blocks_its_own_table() {
+        let gate = SeatingGate::default();
+        let _closing = gate.closing(3).await;
+        let other = tokio::time::timeout(Duration::from_millis(50), gate.ordering(4)).await;
+        assert!(other.is_ok());
+        let same = tokio::time::timeout(Duration::from_millis(50), gate.ordering(3)).await;
+        assert!(same.is_err());
+    }
+}
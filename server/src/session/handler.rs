@@ -0,0 +1,258 @@
+use crate::app_state::AppState;
+use crate::error::ApiError;
+use crate::locale::PreferredLocales;
+use crate::order::model::OrderResponse;
+use crate::session::model::{check_guests, OpenSessionRequest, TableSession};
+use crate::session::repository::OpenOutcome;
+use crate::table::handler::ensure_table_exists;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::Utc;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+/// Seat a party: the table takes orders until the session is closed.
+pub async fn open_table_session(
+    State(state): State<Arc<AppState>>,
+    Path(table_id): Path<u32>,
+    Json(payload): Json<OpenSessionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    check_guests(payload.guests).map_err(ApiError::BadRequest)?;
+    let session = TableSession::open(table_id, payload.guests, payload.allergens, Utc::now());
+    match state.sessions.open(session.clone()).await? {
+        OpenOutcome::Opened => {
+            info!(
+                "Seated {} guests at table {} (session {})",
+                session.guests, table_id, session.id
+            );
+            Ok((
+                StatusCode::CREATED,
+                Json(json!({ "status": "success", "data": session })),
+            ))
+        }
+        OpenOutcome::AlreadyOpen(existing) => Err(ApiError::Conflict(format!(
+            "Table {} already has an open session {}",
+            table_id, existing.id
+        ))),
+    }
+}
+
+pub async fn get_table_session(
+    State(state): State<Arc<AppState>>,
+    Path(table_id): Path<u32>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    let session = state
+        .sessions
+        .active(table_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(json!({ "status": "success", "data": session })))
+}
+
+/// The party leaves: archive every order of the session, then close it, so the
+/// next party starts with an empty table. A close that fails after the archive
+/// can be retried; the response lists all orders the session ever archived.
+pub async fn close_table_session(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path(table_id): Path<u32>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    let now = Utc::now();
+    let (session, moved) = {
+        let _seating = state.seating.closing(table_id).await;
+        let session = state
+            .sessions
+            .active(table_id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        let moved = state.orders.archive_session(table_id, session.id).await?;
+        let session = state
+            .sessions
+            .close(table_id, now)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        (session, moved)
+    };
+    info!(
+        "Closed session {} of table {}, archived {} orders",
+        session.id,
+        table_id,
+        moved.len()
+    );
+    let orders: Vec<OrderResponse> = state
+        .orders
+        .list_archived(table_id)
+        .await?
+        .into_iter()
+        .filter(|order| order.session_id == Some(session.id))
+        .map(|order| OrderResponse::at(order, now).localized(&locales))
+        .collect();
+    Ok(Json(json!({
+        "status": "success",
+        "data": { "session": session, "orders": orders }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::handler::create_orders;
+    use crate::order::model::{CreateOrderRequest, MenuData};
+    use crate::session::repository::{
+        InMemorySessionRepository, RepositoryError, SessionRepository, SessionUpdate,
+    };
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Sessions whose next close fails once, as if the disk went away mid-close.
+    struct FailingClose {
+        inner: InMemorySessionRepository,
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl SessionRepository for FailingClose {
+        async fn active(&self, table_id: u32) -> Result<Option<TableSession>, RepositoryError> {
+            self.inner.active(table_id).await
+        }
+
+        async fn open(&self, session: TableSession) -> Result<OpenOutcome, RepositoryError> {
+            self.inner.open(session).await
+        }
+
+        async fn update(
+            &self,
+            table_id: u32,
+            apply: SessionUpdate,
+        ) -> Result<Option<TableSession>, RepositoryError> {
+            self.inner.update(table_id, apply).await
+        }
+
+        async fn close(
+            &self,
+            table_id: u32,
+            at: DateTime<Utc>,
+        ) -> Result<Option<TableSession>, RepositoryError> {
+            if self.fail.swap(false, Ordering::SeqCst) {
+                return Err(RepositoryError::Backend("disk unavailable".to_string()));
+            }
+            self.inner.close(table_id, at).await
+        }
+    }
+
+    async fn seat(state: &Arc<AppState>, table_id: u32, guests: u32) -> Result<(), ApiError> {
+        open_table_session(
+            State(state.clone()),
+            Path(table_id),
+            Json(OpenSessionRequest {
+                guests,
+                allergens: Vec::new(),
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn order_ramen(state: &Arc<AppState>, table_id: u32) -> Result<(), ApiError> {
+        let menus = state.menus.list().await.unwrap();
+        let ramen = menus.iter().find(|menu| menu.name == "Ramen").unwrap();
+        let payload = CreateOrderRequest {
+            table_id,
+            menus: vec![MenuData::from_menu(ramen).into()],
+        };
+        create_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Json(payload),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn test_session_gates_orders_and_archives_them_on_close() {
+        let state = Arc::new(AppState::new());
+        let error = order_ramen(&state, 12).await.err().unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        let error = seat(&state, 12, 0).await.err().unwrap();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        seat(&state, 12, 3).await.unwrap();
+        let error = seat(&state, 12, 2).await.err().unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        order_ramen(&state, 12).await.unwrap();
+        let session = state.sessions.active(12).await.unwrap().unwrap();
+        assert_eq!(session.guests, 3);
+        let orders = state.orders.list_by_table(12).await.unwrap();
+        assert_eq!(orders[0].session_id, Some(session.id));
+
+        let response =
+            close_table_session(State(state.clone()), PreferredLocales::default(), Path(12))
+                .await
+                .unwrap()
+                .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["session"]["id"], session.id.to_string());
+        assert_eq!(json["data"]["orders"].as_array().unwrap().len(), 1);
+        assert!(state.orders.list_by_table(12).await.unwrap().is_empty());
+        assert_eq!(state.orders.list_archived(12).await.unwrap().len(), 1);
+
+        let error = get_table_session(State(state.clone()), Path(12))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+        let error = order_ramen(&state, 12).await.err().unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        // The next party gets the table.
+        seat(&state, 12, 2).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_close_can_be_retried() {
+        let state = Arc::new(AppState::new().with_session_storage(Arc::new(FailingClose {
+            inner: InMemorySessionRepository::new(Vec::new()),
+            fail: AtomicBool::new(true),
+        })));
+        seat(&state, 12, 2).await.unwrap();
+        order_ramen(&state, 12).await.unwrap();
+        let session = state.sessions.active(12).await.unwrap().unwrap();
+
+        let error =
+            close_table_session(State(state.clone()), PreferredLocales::default(), Path(12))
+                .await
+                .err()
+                .unwrap();
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        // The orders are archived already, but the session is still there to close.
+        assert!(state.orders.list_by_table(12).await.unwrap().is_empty());
+        assert_eq!(
+            state.sessions.active(12).await.unwrap().unwrap().id,
+            session.id
+        );
+
+        let response =
+            close_table_session(State(state.clone()), PreferredLocales::default(), Path(12))
+                .await
+                .unwrap()
+                .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["session"]["id"], session.id.to_string());
+        assert_eq!(json["data"]["orders"].as_array().unwrap().len(), 1);
+        assert!(state.sessions.active(12).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod gate;
+pub mod handler;
+pub mod model;
+pub mod repository;
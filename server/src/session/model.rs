@@ -0,0 +1,57 @@
+use crate::menu::model::Allergen;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Largest party one table session can seat.
+pub const MAX_GUESTS: u32 = 30;
+
+/// One party's visit to a table, from being seated until they leave. Orders
+/// are only taken while the table has an open session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSession {
+    pub id: Uuid,
+    pub table_id: u32,
+    pub guests: u32,
+    /// Allergens the party told staff about. Their orders containing any are flagged.
+    #[serde(default)]
+    pub allergens: Vec<Allergen>,
+    pub opened_at: DateTime<Utc>,
+    #[serde(default)]
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl TableSession {
+    pub fn open(table_id: u32, guests: u32, allergens: Vec<Allergen>, at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            table_id,
+            guests,
+            allergens: normalize_allergens(allergens),
+            opened_at: at,
+            closed_at: None,
+        }
+    }
+}
+
+/// Body of `POST /tables/:id/session`.
+#[derive(Debug, Deserialize)]
+pub struct OpenSessionRequest {
+    pub guests: u32,
+    #[serde(default)]
+    pub allergens: Vec<Allergen>,
+}
+
+pub fn check_guests(guests: u32) -> Result<(), String> {
+    if !(1..=MAX_GUESTS).contains(&guests) {
+        return Err(format!("Guest count must be 1-{}", MAX_GUESTS));
+    }
+    Ok(())
+}
+
+/// Sorted and deduplicated, as stored on the session.
+pub fn normalize_allergens(mut allergens: Vec<Allergen>) -> Vec<Allergen> {
+    allergens.sort();
+    allergens.dedup();
+    allergens
+}
@@ -0,0 +1,165 @@
+use crate::session::model::TableSession;
+use crate::session::repository::{OpenOutcome, SessionRepository, SessionUpdate};
+use crate::storage::file::{append_line, read_or_default, write_atomically};
+use crate::storage::RepositoryError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+/// Open sessions by table. With a `file`, every change rewrites it so seated tables
+/// survive restarts, and closed sessions are appended to the `.history` file next to
+/// it as the visit history, like the sqlite backend keeps them. Without a file there
+/// is nowhere to keep history, so closed sessions are dropped; their orders live on
+/// in the order archive either way.
+pub struct InMemorySessionRepository {
+    open: RwLock<HashMap<u32, TableSession>>,
+    file: Option<PathBuf>,
+}
+
+impl InMemorySessionRepository {
+    pub fn new(sessions: Vec<TableSession>) -> Self {
+        Self {
+            open: RwLock::new(
+                sessions
+                    .into_iter()
+                    .filter(|session| session.closed_at.is_none())
+                    .map(|session| (session.table_id, session))
+                    .collect(),
+            ),
+            file: None,
+        }
+    }
+
+    /// Load the sessions kept at `path`, starting empty when the file does not exist yet.
+    pub async fn open_file(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        let path = path.as_ref().to_path_buf();
+        let sessions: Vec<TableSession> = read_or_default(&path).await?;
+        Ok(Self {
+            file: Some(path),
+            ..Self::new(sessions)
+        })
+    }
+
+    /// Called with the write lock held so concurrent changes hit the disk in order.
+    async fn persist(&self, open: &HashMap<u32, TableSession>) -> Result<(), RepositoryError> {
+        match &self.file {
+            Some(path) => {
+                let sessions: Vec<&TableSession> = open.values().collect();
+                write_atomically(path, &sessions).await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// One JSON line per closed session, oldest first. Written before the session
+    /// leaves the open file, so a crash in between can only repeat a visit, with the
+    /// later line the one that counts.
+    async fn record_closed(&self, session: &TableSession) -> Result<(), RepositoryError> {
+        match &self.file {
+            Some(path) => append_line(&path.with_extension("history"), session).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for InMemorySessionRepository {
+    async fn active(&self, table_id: u32) -> Result<Option<TableSession>, RepositoryError> {
+        Ok(self.open.read().await.get(&table_id).cloned())
+    }
+
+    async fn open(&self, session: TableSession) -> Result<OpenOutcome, RepositoryError> {
+        let mut open = self.open.write().await;
+        if let Some(existing) = open.get(&session.table_id) {
+            return Ok(OpenOutcome::AlreadyOpen(existing.clone()));
+        }
+        let mut next = open.clone();
+        next.insert(session.table_id, session);
+        self.persist(&next).await?;
+        *open = next;
+        Ok(OpenOutcome::Opened)
+    }
+
+    async fn update(
+        &self,
+        table_id: u32,
+        apply: SessionUpdate,
+    ) -> Result<Option<TableSession>, RepositoryError> {
+        let mut open = self.open.write().await;
+        let Some(session) = open.get(&table_id) else {
+            return Ok(None);
+        };
+        let mut session = session.clone();
+        apply(&mut session);
+        let mut next = open.clone();
+        next.insert(table_id, session.clone());
+        self.persist(&next).await?;
+        *open = next;
+        Ok(Some(session))
+    }
+
+    async fn close(
+        &self,
+        table_id: u32,
+        at: DateTime<Utc>,
+    ) -> Result<Option<TableSession>, RepositoryError> {
+        let mut open = self.open.write().await;
+        let mut next = open.clone();
+        let Some(mut session) = next.remove(&table_id) else {
+            return Ok(None);
+        };
+        session.closed_at = Some(at);
+        self.record_closed(&session).await?;
+        self.persist(&next).await?;
+        *open = next;
+        Ok(Some(session))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_one_open_session_per_table_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("mealdiner-{}.json", uuid::Uuid::new_v4()));
+        let session = TableSession::open(4, 2, Vec::new(), Utc::now());
+        {
+            let repository = InMemorySessionRepository::open_file(&path).await.unwrap();
+            let outcome = repository.open(session.clone()).await.unwrap();
+            assert!(matches!(outcome, OpenOutcome::Opened));
+            let outcome = repository
+                .open(TableSession::open(4, 5, Vec::new(), Utc::now()))
+                .await
+                .unwrap();
+            assert!(matches!(outcome, OpenOutcome::AlreadyOpen(open) if open.id == session.id));
+        }
+
+        let repository = InMemorySessionRepository::open_file(&path).await.unwrap();
+        assert_eq!(repository.active(4).await.unwrap(), Some(session.clone()));
+        let closed = repository.close(4, Utc::now()).await.unwrap().unwrap();
+        assert!(closed.closed_at.is_some());
+        assert!(repository.active(4).await.unwrap().is_none());
+        assert!(repository.close(4, Utc::now()).await.unwrap().is_none());
+
+        // The closed session is appended to the history and stays closed after a restart.
+        let repository = InMemorySessionRepository::open_file(&path).await.unwrap();
+        assert!(repository.active(4).await.unwrap().is_none());
+        let next = TableSession::open(4, 3, Vec::new(), Utc::now());
+        repository.open(next.clone()).await.unwrap();
+        repository.close(4, Utc::now()).await.unwrap();
+        let history = std::fs::read_to_string(path.with_extension("history")).unwrap();
+        let visits: Vec<TableSession> = history
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(visits.len(), 2);
+        assert_eq!(visits[0], closed);
+        assert_eq!(visits[1].id, next.id);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("history")).unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+pub mod memory;
+pub mod sqlite;
+
+use crate::session::model::TableSession;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+pub use memory::InMemorySessionRepository;
+pub use sqlite::SqliteSessionRepository;
+
+pub use crate::storage::RepositoryError;
+
+/// A change to a table's open session, run while the backend holds it exclusively.
+pub type SessionUpdate = Box<dyn FnOnce(&mut TableSession) + Send>;
+
+#[derive(Debug)]
+pub enum OpenOutcome {
+    Opened,
+    /// The table is still taken; holds the session that has it.
+    AlreadyOpen(TableSession),
+}
+
+/// Storage boundary for table sessions. A table has at most one open session;
+/// persistent backends keep closed sessions as the visit history.
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    /// The table's open session, if a party is seated there.
+    async fn active(&self, table_id: u32) -> Result<Option<TableSession>, RepositoryError>;
+
+    /// Store `session` as its table's open session, unless one is open already.
+    async fn open(&self, session: TableSession) -> Result<OpenOutcome, RepositoryError>;
+
+    /// Change the table's open session. `None` when no session is open.
+    async fn update(
+        &self,
+        table_id: u32,
+        apply: SessionUpdate,
+    ) -> Result<Option<TableSession>, RepositoryError>;
+
+    /// Close the table's open session at `at`, returning it. `None` when no session is open.
+    async fn close(
+        &self,
+        table_id: u32,
+        at: DateTime<Utc>,
+    ) -> Result<Option<TableSession>, RepositoryError>;
+}
@@ -0,0 +1,163 @@
+use crate::session::model::TableSession;
+use crate::session::repository::{OpenOutcome, SessionRepository, SessionUpdate};
+use crate::storage::{RepositoryError, SqliteDatabase};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+/// Durable backend. Closed sessions stay in the table as the visit history; a
+/// partial unique index keeps one open session per table.
+pub struct SqliteSessionRepository {
+    db: SqliteDatabase,
+}
+
+impl SqliteSessionRepository {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+}
+
+fn open_session(conn: &Connection, table_id: u32) -> Result<Option<TableSession>, RepositoryError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT payload FROM table_sessions WHERE table_id = ?1 AND closed_at IS NULL",
+    )?;
+    let mut rows = stmt.query(params![table_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(serde_json::from_str(&row.get::<_, String>(0)?)?)),
+        None => Ok(None),
+    }
+}
+
+fn store(conn: &Connection, session: &TableSession) -> Result<(), RepositoryError> {
+    conn.execute(
+        "UPDATE table_sessions SET closed_at = ?1, payload = ?2 WHERE id = ?3",
+        params![
+            session.closed_at.map(|at| at.to_rfc3339()),
+            serde_json::to_string(session)?,
+            session.id.to_string()
+        ],
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl SessionRepository for SqliteSessionRepository {
+    async fn active(&self, table_id: u32) -> Result<Option<TableSession>, RepositoryError> {
+        self.db.run(move |conn| open_session(conn, table_id)).await
+    }
+
+    async fn open(&self, session: TableSession) -> Result<OpenOutcome, RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                if let Some(existing) = open_session(&tx, session.table_id)? {
+                    return Ok(OpenOutcome::AlreadyOpen(existing));
+                }
+                tx.execute(
+                    "INSERT INTO table_sessions (id, table_id, closed_at, payload)
+                    VALUES (?1, ?2, NULL, ?3)",
+                    params![
+                        session.id.to_string(),
+                        session.table_id,
+                        serde_json::to_string(&session)?
+                    ],
+                )?;
+                tx.commit()?;
+                Ok(OpenOutcome::Opened)
+            })
+            .await
+    }
+
+    async fn update(
+        &self,
+        table_id: u32,
+        apply: SessionUpdate,
+    ) -> Result<Option<TableSession>, RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                let Some(mut session) = open_session(&tx, table_id)? else {
+                    return Ok(None);
+                };
+                apply(&mut session);
+                store(&tx, &session)?;
+                tx.commit()?;
+                Ok(Some(session))
+            })
+            .await
+    }
+
+    async fn close(
+        &self,
+        table_id: u32,
+        at: DateTime<Utc>,
+    ) -> Result<Option<TableSession>, RepositoryError> {
+        self.db
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                let Some(mut session) = open_session(&tx, table_id)? else {
+                    return Ok(None);
+                };
+                session.closed_at = Some(at);
+                store(&tx, &session)?;
+                tx.commit()?;
+                Ok(Some(session))
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::model::Allergen;
+
+    #[tokio::test]
+    async fn test_open_update_close() {
+        let repository = SqliteSessionRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let session = TableSession::open(12, 3, Vec::new(), Utc::now());
+        let outcome = repository.open(session.clone()).await.unwrap();
+        assert!(matches!(outcome, OpenOutcome::Opened));
+        let outcome = repository
+            .open(TableSession::open(12, 2, Vec::new(), Utc::now()))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, OpenOutcome::AlreadyOpen(_)));
+
+        let updated = repository
+            .update(12, Box::new(|s| s.allergens = vec![Allergen::Egg]))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.allergens, [Allergen::Egg]);
+        assert_eq!(repository.active(12).await.unwrap(), Some(updated));
+
+        let closed = repository.close(12, Utc::now()).await.unwrap().unwrap();
+        assert_eq!(closed.id, session.id);
+        assert!(repository.active(12).await.unwrap().is_none());
+        assert!(repository
+            .update(12, Box::new(|_| {}))
+            .await
+            .unwrap()
+            .is_none());
+
+        // The table is free for the next party, and the closed visit stays as history.
+        let outcome = repository
+            .open(TableSession::open(12, 4, Vec::new(), Utc::now()))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, OpenOutcome::Opened));
+        let closed_count: u32 = repository
+            .db
+            .run(|conn| {
+                Ok(conn.query_row(
+                    "SELECT COUNT(*) FROM table_sessions WHERE closed_at IS NOT NULL",
+                    [],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+            .unwrap();
+        assert_eq!(closed_count, 1);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::storage::RepositoryError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// Read a JSON file written by `write_atomically`, or `T::default()` when it does
+/// not exist yet.
+pub async fn read_or_default<T: DeserializeOwned + Default>(
+    path: &Path,
+) -> Result<T, RepositoryError> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace `path` with `value` as JSON. The content is synced to disk before the
+/// rename, so a crash leaves either the old file or the complete new one.
+pub async fn write_atomically<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> Result<(), RepositoryError> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    let mut tmp = File::create(&tmp_path).await?;
+    tmp.write_all(&serde_json::to_vec(value)?).await?;
+    tmp.sync_all().await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+/// Append `value` to `path` as one JSON line and sync it, creating the file if needed.
+pub async fn append_line<T: Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> Result<(), RepositoryError> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(&line).await?;
+    file.sync_data().await?;
+    Ok(())
+}
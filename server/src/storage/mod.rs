@@ -0,0 +1,33 @@
+pub mod file;
+pub mod sqlite;
+
+use thiserror::Error;
+
+pub use sqlite::SqliteDatabase;
+
+/// Failure shared by every repository, whatever it stores.
+#[derive(Error, Debug, Clone)]
+pub enum RepositoryError {
+    #[error("Storage backend failure: {0}")]
+    Backend(String),
+    #[error("{0}")]
+    Duplicate(String),
+}
+
+impl From<std::io::Error> for RepositoryError {
+    fn from(e: std::io::Error) -> Self {
+        RepositoryError::Backend(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for RepositoryError {
+    fn from(e: serde_json::Error) -> Self {
+        RepositoryError::Backend(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for RepositoryError {
+    fn from(e: rusqlite::Error) -> Self {
+        RepositoryError::Backend(e.to_string())
+    }
+}
@@ -0,0 +1,120 @@
+use crate::storage::RepositoryError;
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Ordered schema migrations. `PRAGMA user_version` records how many have been applied,
+/// so only append to this list - never edit an entry that has already shipped.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE orders (
+        id TEXT PRIMARY KEY NOT NULL,
+        table_id INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX idx_orders_table_id ON orders (table_id);",
+    "ALTER TABLE orders ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+    UPDATE orders SET status = COALESCE(json_extract(payload, '$.status'), 'pending');
+    CREATE INDEX idx_orders_status ON orders (status);",
+    "CREATE TABLE menus (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        retired INTEGER NOT NULL DEFAULT 0,
+        payload TEXT NOT NULL
+    );
+    CREATE UNIQUE INDEX idx_menus_active_name ON menus (name COLLATE NOCASE) WHERE retired = 0;",
+    "CREATE TABLE menu_stock (
+        menu_id TEXT PRIMARY KEY NOT NULL REFERENCES menus (id),
+        quantity INTEGER NOT NULL CHECK (quantity >= 0)
+    );",
+    "ALTER TABLE orders ADD COLUMN ticket_id TEXT;
+    UPDATE orders SET ticket_id = json_extract(payload, '$.ticket.id');
+    CREATE INDEX idx_orders_ticket_id ON orders (ticket_id);",
+    "CREATE TABLE archived_orders (
+        id TEXT PRIMARY KEY NOT NULL,
+        table_id INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX idx_archived_orders_table_id ON archived_orders (table_id);",
+    "CREATE TABLE table_sessions (
+        id TEXT PRIMARY KEY NOT NULL,
+        table_id INTEGER NOT NULL,
+        closed_at TEXT,
+        payload TEXT NOT NULL
+    );
+    CREATE UNIQUE INDEX idx_table_sessions_open ON table_sessions (table_id) WHERE closed_at IS NULL;",
+];
+
+/// One SQLite file shared by every SQLite-backed repository, so a single
+/// migration history covers all of their tables.
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        let conn = Connection::open(path.as_ref())?;
+        // WAL keeps readers from blocking on the single writer.
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::from_connection(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, RepositoryError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self, RepositoryError> {
+        run_migrations(&mut conn)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// rusqlite is blocking, so every statement runs on the blocking thread pool.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, RepositoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, RepositoryError> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| RepositoryError::Backend("SQLite connection poisoned".to_string()))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| RepositoryError::Backend(e.to_string()))?
+    }
+}
+
+fn run_migrations(conn: &mut Connection) -> Result<(), RepositoryError> {
+    let applied: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version + 1)?;
+        tx.commit()?;
+        info!("Applied SQLite migration {}", version + 1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        run_migrations(&mut conn).unwrap();
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+}
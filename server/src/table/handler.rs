@@ -1,41 +1,94 @@
 use crate::app_state::AppState;
-use axum::extract::{Path, State};
+use crate::config::handler::get_config_internally;
+use crate::error::ApiError;
+use crate::handler::internal_error;
+use crate::locale::PreferredLocales;
+use crate::menu::modifier::select_modifiers;
+use crate::menu::repository::ReserveOutcome;
+use crate::order::model::{
+    check_quantity, normalize_note, AmendOrderRequest, LineDetails, Order, OrderResponse,
+    OrderStatus, UpdateOrderStatusRequest,
+};
+use crate::order::repository::UpdateOutcome;
+use crate::order::ticket::TicketResponse;
+use crate::session::model::normalize_allergens;
+use crate::table::model::{AllergenDeclarationRequest, ClearTableQuery};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
+use chrono::Utc;
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
+/// Refuse table numbers outside the configured range.
+pub fn ensure_table_exists(table_id: u32) -> Result<(), ApiError> {
+    let config = get_config_internally();
+    if !(config.table_range.0..=config.table_range.1).contains(&table_id) {
+        return Err(ApiError::BadRequest(format!(
+            "Table {} does not exist",
+            table_id
+        )));
+    }
+    Ok(())
+}
+
 pub async fn get_table_orders(
     State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
     Path(table_id): Path<u32>,
-) -> impl IntoResponse {
-    let orders = state.orders.read().await;
-    let table_orders = orders.get(&table_id).cloned().unwrap_or_default();
+) -> Result<impl IntoResponse, StatusCode> {
+    let table_orders = state
+        .orders
+        .list_by_table(table_id)
+        .await
+        .map_err(internal_error)?;
     debug!("get_table_order: {:?}", table_orders);
+    let now = Utc::now();
+    let table_orders: Vec<OrderResponse> = table_orders
+        .into_iter()
+        .map(|order| OrderResponse::at(order, now).localized(&locales))
+        .collect();
     let response = json!({
         "status": "success",
         "data": table_orders
     });
-    Json(response)
+    Ok(Json(response))
+}
+
+/// The table's orders grouped by the request they were placed with.
+pub async fn get_table_tickets(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path(table_id): Path<u32>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let table_orders = state
+        .orders
+        .list_by_table(table_id)
+        .await
+        .map_err(internal_error)?;
+    let tickets = TicketResponse::group(table_orders, Utc::now(), &locales);
+    Ok(Json(json!({ "status": "success", "data": tickets })))
 }
 
 pub async fn get_table_order(
     State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
     Path((table_id, order_id)): Path<(u32, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let orders = state.orders.read().await;
-    match orders.get(&table_id) {
-        Some(table_orders) => {
-            if let Some(order) = table_orders.iter().find(|order| order.id == order_id) {
-                info!("get_table_order: {:?}", order);
-                let response = json!({ "status": "success", "data": order });
-                Ok(Json(response))
-            } else {
-                Err(StatusCode::NOT_FOUND)
-            }
+    match state
+        .orders
+        .get(table_id, order_id)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(order) => {
+            info!("get_table_order: {:?}", order);
+            let response = json!({ "status": "success", "data": OrderResponse::from(order).localized(&locales) });
+            Ok(Json(response))
         }
         None => Err(StatusCode::NOT_FOUND),
     }
@@ -45,56 +98,286 @@ pub async fn delete_table_order(
     State(state): State<Arc<AppState>>,
     Path((table_id, order_id)): Path<(u32, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut orders = state.orders.write().await;
-    if let Some(table_orders) = orders.get_mut(&table_id) {
-        let current_size = table_orders.len();
-        table_orders.retain(|order| order.id != order_id);
-        // Succeed to remove
-        if table_orders.len() < current_size {
-            info!("deleting order {} on table {}", current_size, table_id);
-            Ok(StatusCode::NO_CONTENT)
-        } else {
-            Err(StatusCode::NOT_FOUND)
+    let order = state
+        .orders
+        .get(table_id, order_id)
+        .await
+        .map_err(internal_error)?;
+    let removed = state
+        .orders
+        .delete(table_id, order_id)
+        .await
+        .map_err(internal_error)?;
+    // Succeed to remove
+    if let Some(order) = order.filter(|_| removed) {
+        info!("deleting order {} on table {}", order_id, table_id);
+        if order.status.is_unfinished() {
+            give_back_stock(&state, &order).await;
         }
+        Ok(StatusCode::NO_CONTENT)
     } else {
         Err(StatusCode::NOT_FOUND)
     }
 }
 
+/// Clear a table in one step when guests leave. Every order goes, or with
+/// `keep_unserved` only the served and cancelled ones. Returns what was taken off.
+pub async fn clear_table_orders(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path(table_id): Path<u32>,
+    Query(query): Query<ClearTableQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    let statuses: Vec<OrderStatus> = OrderStatus::ALL
+        .into_iter()
+        .filter(|status| !query.keep_unserved || status.is_terminal())
+        .collect();
+    let removed = state
+        .orders
+        .clear_table(table_id, &statuses, query.archive)
+        .await?;
+    info!(
+        "{} {} orders of table {}",
+        if query.archive { "archived" } else { "deleted" },
+        removed.len(),
+        table_id
+    );
+    let now = Utc::now();
+    let removed: Vec<OrderResponse> = removed
+        .into_iter()
+        .map(|order| OrderResponse::at(order, now).localized(&locales))
+        .collect();
+    Ok(Json(json!({ "status": "success", "data": removed })))
+}
+
+/// Orders a table clear archived, oldest first.
+pub async fn get_archived_table_orders(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path(table_id): Path<u32>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    let now = Utc::now();
+    let archived: Vec<OrderResponse> = state
+        .orders
+        .list_archived(table_id)
+        .await?
+        .into_iter()
+        .map(|order| OrderResponse::at(order, now).localized(&locales))
+        .collect();
+    Ok(Json(json!({ "status": "success", "data": archived })))
+}
+
+/// Move an order through its lifecycle. Illegal transitions are refused with a 409.
+pub async fn update_table_order_status(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path((table_id, order_id)): Path<(u32, Uuid)>,
+    Json(payload): Json<UpdateOrderStatusRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let next = payload.status;
+    let outcome = state
+        .orders
+        .update(
+            table_id,
+            order_id,
+            Box::new(move |order| order.transition_to(next)),
+        )
+        .await?;
+    match outcome {
+        UpdateOutcome::Updated(order) => {
+            info!("order {} on table {} is now {}", order_id, table_id, next);
+            if next == OrderStatus::Cancelled {
+                give_back_stock(&state, &order).await;
+            }
+            let data = OrderResponse::from(*order).localized(&locales);
+            Ok(Json(json!({ "status": "success", "data": data })))
+        }
+        UpdateOutcome::Rejected(reason) => Err(ApiError::Conflict(reason.to_string())),
+        UpdateOutcome::NotFound => Err(ApiError::NotFound),
+    }
+}
+
+/// Change quantity, note or modifiers of an order that is still pending. The
+/// order keeps its id and place in the queue; stock follows the new quantity.
+pub async fn amend_table_order(
+    State(state): State<Arc<AppState>>,
+    locales: PreferredLocales,
+    Path((table_id, order_id)): Path<(u32, Uuid)>,
+    Json(payload): Json<AmendOrderRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let order = state
+        .orders
+        .get(table_id, order_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let before = order.details();
+    let quantity = payload.quantity.unwrap_or(before.quantity);
+    check_quantity(quantity).map_err(ApiError::BadRequest)?;
+    let note = match payload.note {
+        Some(note) => normalize_note(Some(&note)).map_err(ApiError::BadRequest)?,
+        None => before.note.clone(),
+    };
+    let modifiers = match payload.modifiers {
+        Some(choices) => {
+            let menu = state
+                .menus
+                .get(order.menu.id)
+                .await?
+                .filter(|menu| !menu.retired)
+                .ok_or_else(|| {
+                    ApiError::Conflict(format!(
+                        "Menu '{}' is no longer offered, so its modifiers cannot change",
+                        order.menu.name
+                    ))
+                })?;
+            select_modifiers(&menu.modifier_groups, &choices).map_err(ApiError::BadRequest)?
+        }
+        None => before.modifiers.clone(),
+    };
+    let after = LineDetails {
+        quantity,
+        note,
+        modifiers,
+    };
+
+    // Take any extra portions before changing the order, and give them back if
+    // the change is refused.
+    let extra = portions_of(order.menu.id, quantity.saturating_sub(before.quantity));
+    if extra.is_empty() {
+        // Nothing to take.
+    } else if let ReserveOutcome::Short(short) = state.menus.reserve_stock(&extra).await? {
+        let available = short.first().map_or(0, |shortage| shortage.available);
+        return Err(ApiError::Conflict(format!(
+            "Only {} more '{}' left",
+            available, order.menu.name
+        )));
+    }
+    let expected = before.clone();
+    let now = Utc::now();
+    let outcome = state
+        .orders
+        .update(
+            table_id,
+            order_id,
+            Box::new(move |order| order.amend(&expected, after, now)),
+        )
+        .await;
+    let returned = match &outcome {
+        Ok(UpdateOutcome::Updated(order)) => portions_of(
+            order.menu.id,
+            before.quantity.saturating_sub(order.quantity),
+        ),
+        _ => extra,
+    };
+    if !returned.is_empty() {
+        if let Err(e) = state.menus.release_stock(&returned).await {
+            error!("Failed to give back stock after amending an order: {}", e);
+        }
+    }
+    match outcome? {
+        UpdateOutcome::Updated(order) => {
+            info!("order {} on table {} amended", order_id, table_id);
+            let data = OrderResponse::at(*order, now).localized(&locales);
+            Ok(Json(json!({ "status": "success", "data": data })))
+        }
+        UpdateOutcome::Rejected(reason) => Err(ApiError::Conflict(reason.to_string())),
+        UpdateOutcome::NotFound => Err(ApiError::NotFound),
+    }
+}
+
+/// Return the stock an order took when it is cancelled or deleted before the kitchen
+/// finished it. A set's own portion goes back with the last of its live portions.
+pub async fn give_back_stock(state: &AppState, order: &Order) {
+    let mut portions = portions_of(order.menu.id, order.quantity);
+    if let Some(combo) = &order.combo {
+        match state.orders.list_by_table(order.table_id).await {
+            Ok(orders) => {
+                let set_still_ordered = orders.iter().any(|other| {
+                    other.id != order.id
+                        && other.status != OrderStatus::Cancelled
+                        && other
+                            .combo
+                            .as_ref()
+                            .is_some_and(|other| other.line_id == combo.line_id)
+                });
+                if !set_still_ordered {
+                    *portions.entry(combo.menu.id).or_insert(0) += order.quantity;
+                }
+            }
+            Err(e) => error!("Failed to look up the rest of set {}: {}", combo.line_id, e),
+        }
+    }
+    if let Err(e) = state.menus.release_stock(&portions).await {
+        error!("Failed to give back stock of order {}: {}", order.id, e);
+    }
+}
+
+fn portions_of(menu_id: Uuid, count: u32) -> HashMap<Uuid, u32> {
+    if count == 0 {
+        HashMap::new()
+    } else {
+        HashMap::from([(menu_id, count)])
+    }
+}
+
+/// What the seated party declared. A table without a session has declared nothing.
+pub async fn get_table_allergens(
+    State(state): State<Arc<AppState>>,
+    Path(table_id): Path<u32>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    let allergens = state
+        .sessions
+        .active(table_id)
+        .await?
+        .map(|session| session.allergens)
+        .unwrap_or_default();
+    Ok(Json(json!({ "status": "success", "data": allergens })))
+}
+
+/// Staff record what the seated party must avoid. New orders containing any
+/// of it are flagged, not refused; the guest may still choose the dish. The
+/// declaration ends with the session.
+pub async fn declare_table_allergens(
+    State(state): State<Arc<AppState>>,
+    Path(table_id): Path<u32>,
+    Json(payload): Json<AllergenDeclarationRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    ensure_table_exists(table_id)?;
+    let allergens = normalize_allergens(payload.allergens);
+    let declared = allergens.clone();
+    state
+        .sessions
+        .update(
+            table_id,
+            Box::new(move |session| session.allergens = declared),
+        )
+        .await?
+        .ok_or_else(|| ApiError::Conflict(format!("Table {} has no open session", table_id)))?;
+    info!("Table {} declared allergens {:?}", table_id, allergens);
+    Ok(Json(json!({ "status": "success", "data": allergens })))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::order::model::{MenuData, Order};
+    use crate::menu::model::Price;
+    use crate::order::model::{ComboLine, MenuData, Order, OrderStatus};
+    use crate::order::repository::{OrderRepository, OrderUpdate, RepositoryError};
+    use crate::order::ticket::TicketRef;
+    use crate::session::model::TableSession;
+    use async_trait::async_trait;
     use axum::response::Response;
-    use chrono::Utc;
 
     fn create_test_state() -> Arc<AppState> {
         Arc::new(AppState::new())
     }
 
-    fn create_test_order(table_id: u32) -> Order {
-        Order {
-            id: Uuid::new_v4(),
-            table_id,
-            menu: MenuData {
-                id: Uuid::new_v4(),
-                name: "Test Menu".to_string(),
-            },
-            cooking_time_minutes: 10,
-            created_at: Utc::now(),
-        }
-    }
-
     async fn setup_test_orders(state: &AppState, table_id: u32, count: usize) -> Vec<Order> {
-        let mut orders = Vec::new();
-        {
-            let mut state_orders = state.orders.write().await;
-            for _ in 0..count {
-                let order = create_test_order(table_id);
-                orders.push(order.clone());
-                state_orders.entry(table_id).or_default().push(order);
-            }
-        }
+        let orders: Vec<Order> = (0..count).map(|_| Order::for_test(table_id)).collect();
+        state.orders.create(orders.clone()).await.unwrap();
         orders
     }
 
@@ -114,8 +397,8 @@ mod tests {
             Err(_) => panic!("Expected success response"),
         }
 
-        let orders = state.orders.read().await;
-        assert!(orders.get(&1).unwrap().is_empty());
+        let orders = state.orders.list_by_table(1).await.unwrap();
+        assert!(orders.is_empty());
     }
 
     #[tokio::test]
@@ -128,6 +411,211 @@ mod tests {
             Err(status) => assert_eq!(status, StatusCode::NOT_FOUND),
         }
     }
+    struct UnavailableRepository;
+
+    #[async_trait]
+    impl OrderRepository for UnavailableRepository {
+        async fn create(&self, _orders: Vec<Order>) -> Result<(), RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn list_by_table(&self, _table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn list_by_status(
+            &self,
+            _statuses: &[OrderStatus],
+        ) -> Result<Vec<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn list_by_ticket(&self, _ticket_id: Uuid) -> Result<Vec<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn latest_ticket(&self) -> Result<Option<TicketRef>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn get(
+            &self,
+            _table_id: u32,
+            _order_id: Uuid,
+        ) -> Result<Option<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn get_by_id(&self, _order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn update(
+            &self,
+            _table_id: u32,
+            _order_id: Uuid,
+            _apply: OrderUpdate,
+        ) -> Result<UpdateOutcome, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn delete(&self, _table_id: u32, _order_id: Uuid) -> Result<bool, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn delete_by_id(&self, _order_id: Uuid) -> Result<Option<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn clear_table(
+            &self,
+            _table_id: u32,
+            _statuses: &[OrderStatus],
+            _archive: bool,
+        ) -> Result<Vec<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn archive_session(
+            &self,
+            _table_id: u32,
+            _session_id: Uuid,
+        ) -> Result<Vec<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+
+        async fn list_archived(&self, _table_id: u32) -> Result<Vec<Order>, RepositoryError> {
+            Err(RepositoryError::Backend("unavailable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_storage_failure_maps_to_internal_error() {
+        let state = Arc::new(AppState::with_order_storage(Arc::new(
+            UnavailableRepository,
+        )));
+
+        let response = get_table_orders(State(state.clone()), PreferredLocales::default(), Path(1))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let result = delete_table_order(State(state), Path((1, Uuid::new_v4()))).await;
+        match result {
+            Ok(_) => panic!("Expected error response"),
+            Err(status) => assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    async fn set_status(
+        state: &Arc<AppState>,
+        table_id: u32,
+        order_id: Uuid,
+        status: OrderStatus,
+    ) -> Result<Response, ApiError> {
+        update_table_order_status(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path((table_id, order_id)),
+            Json(UpdateOrderStatusRequest { status }),
+        )
+        .await
+        .map(IntoResponse::into_response)
+    }
+
+    #[tokio::test]
+    async fn test_update_status_happy_path() {
+        let state = create_test_state();
+        let order_id = setup_test_orders(&state, 1, 1).await[0].id;
+
+        for status in [
+            OrderStatus::Cooking,
+            OrderStatus::Ready,
+            OrderStatus::Served,
+        ] {
+            let response = set_status(&state, 1, order_id, status).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), 1024)
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["data"]["status"], status.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_status_illegal_transition() {
+        let state = create_test_state();
+        let order_id = setup_test_orders(&state, 1, 1).await[0].id;
+
+        let error = set_status(&state, 1, order_id, OrderStatus::Served)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        let response = error.into_response();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "fail");
+        assert_eq!(json["message"], "Order cannot move from pending to served");
+
+        set_status(&state, 1, order_id, OrderStatus::Cancelled)
+            .await
+            .unwrap();
+        let error = set_status(&state, 1, order_id, OrderStatus::Cooking)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            error.to_string(),
+            "Order is already cancelled and can no longer change"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_orders_include_countdown() {
+        let state = create_test_state();
+        let order_id = setup_test_orders(&state, 1, 1).await[0].id;
+
+        let response = get_table_order(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path((1, order_id)),
+        )
+        .await
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["data"]["ready_at"].is_string());
+        let remaining = json["data"]["remaining_seconds"].as_i64().unwrap();
+        assert!((590..=600).contains(&remaining));
+
+        set_status(&state, 1, order_id, OrderStatus::Cancelled)
+            .await
+            .unwrap();
+        let response = get_table_orders(State(state), PreferredLocales::default(), Path(1))
+            .await
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"][0]["remaining_seconds"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_update_status_not_found() {
+        let state = create_test_state();
+        let error = set_status(&state, 1, Uuid::new_v4(), OrderStatus::Cooking)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_concurrent_operations() {
         let state = create_test_state();
@@ -138,15 +626,21 @@ mod tests {
 
         let state_clone = state.clone();
         handles.push(tokio::spawn(async move {
-            let response: Response = get_table_orders(State(state_clone), Path(1))
-                .await
-                .into_response();
+            let response: Response =
+                get_table_orders(State(state_clone), PreferredLocales::default(), Path(1))
+                    .await
+                    .into_response();
             assert_eq!(response.status(), StatusCode::OK);
         }));
 
         let state_clone = state.clone();
         handles.push(tokio::spawn(async move {
-            let result = get_table_order(State(state_clone), Path((1, order_id))).await;
+            let result = get_table_order(
+                State(state_clone),
+                PreferredLocales::default(),
+                Path((1, order_id)),
+            )
+            .await;
             match result {
                 Ok(response) => {
                     let response = response.into_response();
@@ -172,4 +666,381 @@ mod tests {
             handle.await.unwrap();
         }
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_concurrent_operations_many_tables() {
+        // Matches the 1,000 branches x 10 tables sizing in the README.
+        const TABLES: u32 = 10_000;
+        let state = create_test_state();
+
+        let mut handles = vec![];
+        for table_id in 1..=TABLES {
+            let state_clone = state.clone();
+            handles.push(tokio::spawn(async move {
+                let orders = setup_test_orders(&state_clone, table_id, 3).await;
+
+                let response: Response = get_table_orders(
+                    State(state_clone.clone()),
+                    PreferredLocales::default(),
+                    Path(table_id),
+                )
+                .await
+                .into_response();
+                assert_eq!(response.status(), StatusCode::OK);
+
+                let result = get_table_order(
+                    State(state_clone.clone()),
+                    PreferredLocales::default(),
+                    Path((table_id, orders[1].id)),
+                )
+                .await;
+                assert!(result.is_ok());
+
+                let result =
+                    delete_table_order(State(state_clone), Path((table_id, orders[0].id))).await;
+                match result {
+                    Ok(response) => {
+                        assert_eq!(response.into_response().status(), StatusCode::NO_CONTENT)
+                    }
+                    Err(_) => panic!("Expected success response"),
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        for table_id in 1..=TABLES {
+            let orders = state.orders.list_by_table(table_id).await.unwrap();
+            assert_eq!(orders.len(), 2, "table {} lost or kept orders", table_id);
+            assert!(orders.iter().all(|order| order.table_id == table_id));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_declare_table_allergens() {
+        use crate::menu::model::Allergen;
+
+        let state = create_test_state();
+        let error = declare_table_allergens(
+            State(state.clone()),
+            Path(5),
+            Json(AllergenDeclarationRequest {
+                allergens: vec![Allergen::Wheat],
+            }),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        let session = TableSession::open(5, 2, Vec::new(), Utc::now());
+        state.sessions.open(session).await.unwrap();
+        let response = declare_table_allergens(
+            State(state.clone()),
+            Path(5),
+            Json(AllergenDeclarationRequest {
+                allergens: vec![Allergen::Wheat, Allergen::Egg, Allergen::Wheat],
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"], serde_json::json!(["egg", "wheat"]));
+        let session = state.sessions.active(5).await.unwrap().unwrap();
+        assert_eq!(session.allergens, [Allergen::Egg, Allergen::Wheat]);
+
+        declare_table_allergens(
+            State(state.clone()),
+            Path(5),
+            Json(AllergenDeclarationRequest { allergens: vec![] }),
+        )
+        .await
+        .unwrap();
+        let session = state.sessions.active(5).await.unwrap().unwrap();
+        assert!(session.allergens.is_empty());
+
+        let error = get_table_allergens(State(state), Path(0))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    }
+
+    async fn amend(
+        state: &Arc<AppState>,
+        order: &Order,
+        payload: serde_json::Value,
+    ) -> Result<Response, ApiError> {
+        amend_table_order(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path((order.table_id, order.id)),
+            Json(serde_json::from_value(payload).unwrap()),
+        )
+        .await
+        .map(IntoResponse::into_response)
+    }
+
+    #[tokio::test]
+    async fn test_amend_order_until_cooking_starts() {
+        let state = create_test_state();
+        let order = setup_test_orders(&state, 2, 1).await.remove(0);
+
+        let response = amend(
+            &state,
+            &order,
+            json!({ "quantity": 3, "note": "  no green onion " }),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["quantity"], 3);
+        assert_eq!(json["data"]["note"], "no green onion");
+        assert_eq!(json["data"]["amendments"][0]["before"]["quantity"], 1);
+
+        amend(&state, &order, json!({ "note": "" })).await.unwrap();
+        let stored = state.orders.get(2, order.id).await.unwrap().unwrap();
+        assert_eq!((stored.quantity, stored.note), (3, None));
+        assert_eq!(stored.amendments.len(), 2);
+        assert_eq!(stored.created_at, order.created_at);
+
+        let error = amend(&state, &order, json!({ "quantity": 0 }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+        set_status(&state, 2, order.id, OrderStatus::Cooking)
+            .await
+            .unwrap();
+        let error = amend(&state, &order, json!({ "quantity": 1 }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        let error = amend(&state, &Order::for_test(2), json!({ "quantity": 1 }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_amend_order_reprices_modifiers_and_moves_stock() {
+        let state = create_test_state();
+        let ramen = state
+            .menus
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|menu| menu.name == "Ramen")
+            .unwrap();
+        state.menus.set_stock(ramen.id, Some(2)).await.unwrap();
+        let order = Order {
+            menu: MenuData::from_menu(&ramen),
+            ..Order::for_test(3)
+        };
+        state.orders.create(vec![order.clone()]).await.unwrap();
+
+        let modifiers = json!([{ "group_id": "toppings", "option_id": "chashu" }]);
+        amend(
+            &state,
+            &order,
+            json!({ "quantity": 3, "modifiers": modifiers }),
+        )
+        .await
+        .unwrap();
+        let stored = state.orders.get(3, order.id).await.unwrap().unwrap();
+        assert_eq!(stored.unit_price, Price::jpy(980 + 250));
+        assert_eq!(stored.modifiers[0].option_name, "Extra chashu");
+        assert_eq!(state.menus.stock_levels().await.unwrap()[&ramen.id], 0);
+
+        let error = amend(&state, &order, json!({ "quantity": 4 }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        let error = amend(
+            &state,
+            &order,
+            json!({ "modifiers": [{ "group_id": "toppings", "option_id": "gold" }] }),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+        amend(&state, &order, json!({ "quantity": 1, "modifiers": [] }))
+            .await
+            .unwrap();
+        let stored = state.orders.get(3, order.id).await.unwrap().unwrap();
+        assert_eq!(stored.unit_price, Price::jpy(980));
+        assert_eq!(state.menus.stock_levels().await.unwrap()[&ramen.id], 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_delete_give_stock_back() {
+        let state = create_test_state();
+        let menus = state.menus.list().await.unwrap();
+        let menu = |name: &str| menus.iter().find(|menu| menu.name == name).unwrap();
+        let (ramen, beer, set) = (menu("Ramen"), menu("Beer"), menu("Beef rice"));
+        for counted in [ramen, beer, set] {
+            state.menus.set_stock(counted.id, Some(0)).await.unwrap();
+        }
+        let stock = |id: Uuid| {
+            let state = state.clone();
+            async move { state.menus.stock_levels().await.unwrap()[&id] }
+        };
+        let pending = Order {
+            menu: MenuData::from_menu(ramen),
+            quantity: 2,
+            ..Order::for_test(4)
+        };
+        let served = Order {
+            menu: MenuData::from_menu(ramen),
+            status: OrderStatus::Served,
+            ..Order::for_test(4)
+        };
+        // Beef rice stands in for a set of one ramen and one beer.
+        let combo = Some(Box::new(ComboLine {
+            line_id: Uuid::new_v4(),
+            menu: MenuData::from_menu(set),
+            unit_price: set.price.clone(),
+        }));
+        let set_ramen = Order {
+            menu: MenuData::from_menu(ramen),
+            combo: combo.clone(),
+            ..Order::for_test(4)
+        };
+        let set_beer = Order {
+            menu: MenuData::from_menu(beer),
+            combo,
+            ..Order::for_test(4)
+        };
+        state
+            .orders
+            .create(vec![
+                pending.clone(),
+                served.clone(),
+                set_ramen.clone(),
+                set_beer.clone(),
+            ])
+            .await
+            .unwrap();
+
+        set_status(&state, 4, pending.id, OrderStatus::Cancelled)
+            .await
+            .unwrap();
+        assert_eq!(stock(ramen.id).await, 2);
+        // Served dishes were eaten; deleting them gives nothing back.
+        delete_table_order(State(state.clone()), Path((4, served.id)))
+            .await
+            .unwrap();
+        assert_eq!(stock(ramen.id).await, 2);
+        // Deleting the already cancelled order does not give it back twice.
+        delete_table_order(State(state.clone()), Path((4, pending.id)))
+            .await
+            .unwrap();
+        assert_eq!(stock(ramen.id).await, 2);
+
+        // The set's own portion goes back with its last portion.
+        set_status(&state, 4, set_ramen.id, OrderStatus::Cancelled)
+            .await
+            .unwrap();
+        assert_eq!((stock(ramen.id).await, stock(set.id).await), (3, 0));
+        delete_table_order(State(state.clone()), Path((4, set_beer.id)))
+            .await
+            .unwrap();
+        assert_eq!((stock(beer.id).await, stock(set.id).await), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_clear_table_keeps_unserved_and_archives() {
+        let state = create_test_state();
+        let orders = setup_test_orders(&state, 7, 3).await;
+        set_status(&state, 7, orders[0].id, OrderStatus::Cancelled)
+            .await
+            .unwrap();
+        setup_test_orders(&state, 8, 1).await;
+
+        let clear = |query: ClearTableQuery| {
+            clear_table_orders(
+                State(state.clone()),
+                PreferredLocales::default(),
+                Path(7),
+                Query(query),
+            )
+        };
+        let response = clear(ClearTableQuery {
+            keep_unserved: true,
+            archive: true,
+        })
+        .await
+        .unwrap()
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"].as_array().unwrap().len(), 1);
+        assert_eq!(json["data"][0]["id"], orders[0].id.to_string());
+        assert_eq!(state.orders.list_by_table(7).await.unwrap().len(), 2);
+
+        clear(ClearTableQuery::default()).await.unwrap();
+        assert!(state.orders.list_by_table(7).await.unwrap().is_empty());
+        assert_eq!(state.orders.list_by_table(8).await.unwrap().len(), 1);
+        let archived = state.orders.list_archived(7).await.unwrap();
+        assert_eq!((archived.len(), archived[0].id), (1, orders[0].id));
+
+        // Clearing an empty table is not an error.
+        clear(ClearTableQuery::default()).await.unwrap();
+        let error = clear_table_orders(
+            State(state.clone()),
+            PreferredLocales::default(),
+            Path(0),
+            Query(ClearTableQuery::default()),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_table_tickets_group_orders() {
+        let state = create_test_state();
+        let first = state.tickets.next();
+        let second = state.tickets.next();
+        let mut orders = Vec::new();
+        for ticket in [Some(first), Some(second), Some(first), None] {
+            orders.push(Order {
+                ticket,
+                ..Order::for_test(4)
+            });
+        }
+        state.orders.create(orders).await.unwrap();
+
+        let response = get_table_tickets(State(state), PreferredLocales::default(), Path(4))
+            .await
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let tickets = json["data"].as_array().unwrap();
+        assert_eq!(tickets.len(), 2);
+        assert_eq!(tickets[0]["number"], first.number);
+        assert_eq!(tickets[0]["items"].as_array().unwrap().len(), 2);
+        assert_eq!(tickets[1]["items"].as_array().unwrap().len(), 1);
+    }
 }
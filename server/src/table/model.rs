@@ -0,0 +1,19 @@
+use crate::menu::model::Allergen;
+use serde::Deserialize;
+
+/// Body of `PUT /tables/:id/allergens`. An empty list clears the declaration.
+#[derive(Debug, Deserialize)]
+pub struct AllergenDeclarationRequest {
+    pub allergens: Vec<Allergen>,
+}
+
+/// Query of `DELETE /tables/:id/orders`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ClearTableQuery {
+    /// Leave orders the kitchen still owes the table (pending, cooking, ready).
+    #[serde(default)]
+    pub keep_unserved: bool,
+    /// Move the orders to the table's archive instead of deleting them.
+    #[serde(default)]
+    pub archive: bool,
+}